@@ -0,0 +1,166 @@
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Fields, Variant};
+
+/// Implements `FormatData` for an enum
+///
+/// Every variant needs to either wrap a single value whose type already implements `FormatData`, 
+/// or be a unit variant marked with `#[format_data(empty)]`, which becomes `FormattedData::EMPTY`.
+/// Anything else is a compile error, e.g. an unmarked unit variant or a variant with more than one field:
+///
+/// ```text
+/// error: unit variants need to be marked with #[format_data(empty)]
+/// error: FormatData can only be derived for variants with a single field
+/// ```
+///
+/// ```ignore
+/// #[derive(FormatData)]
+/// enum MyEnum {
+///     VARCHAR(String),
+///     NUMBER(i64),
+///     DATE(chrono::NaiveDateTime),
+///     #[format_data(empty)]
+///     NONE,
+/// }
+/// ```
+#[proc_macro_derive(FormatData, attributes(format_data))]
+pub fn derive_format_data(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    impl_format_data(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn impl_format_data(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let data = match &input.data {
+        Data::Enum(data) => data,
+        _ => return Err(syn::Error::new_spanned(&input.ident, "FormatData can only be derived for enums")),
+    };
+
+    let arms = data.variants.iter()
+        .map(|variant| variant_arm(name, variant))
+        .collect::<syn::Result<Vec<TokenStream2>>>()?;
+
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::oracle_sql_tools::format_data::FormatData for #name #ty_generics #where_clause {
+            fn fmt_data(self) -> ::oracle_sql_tools::format_data::FormattedData {
+                match self {
+                    #(#arms)*
+                }
+            }
+        }
+    })
+}
+
+fn variant_arm(name: &syn::Ident, variant: &Variant) -> syn::Result<TokenStream2> {
+    let ident = &variant.ident;
+    let is_empty = is_empty_variant(variant)?;
+    match &variant.fields {
+        Fields::Unit if is_empty => Ok(quote! {
+            #name::#ident => ::oracle_sql_tools::format_data::FormattedData::EMPTY,
+        }),
+        Fields::Unit => Err(syn::Error::new_spanned(
+            variant, 
+            "unit variants need to be marked with #[format_data(empty)]"
+        )),
+        _ if is_empty => Err(syn::Error::new_spanned(
+            variant, 
+            "#[format_data(empty)] can only be used on unit variants"
+        )),
+        Fields::Unnamed(fields) if fields.unnamed.len() == 1 => Ok(quote! {
+            #name::#ident(val) => ::oracle_sql_tools::format_data::FormatData::fmt_data(val),
+        }),
+        Fields::Named(fields) if fields.named.len() == 1 => {
+            let field = &fields.named[0].ident;
+            Ok(quote! {
+                #name::#ident { #field: val } => ::oracle_sql_tools::format_data::FormatData::fmt_data(val),
+            })
+        },
+        _ => Err(syn::Error::new_spanned(
+            variant, 
+            "FormatData can only be derived for variants with a single field"
+        )),
+    }
+}
+
+fn is_empty_variant(variant: &Variant) -> syn::Result<bool> {
+    let mut is_empty = false;
+    for attr in variant.attrs.iter().filter(|attr| attr.path().is_ident("format_data")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("empty") {
+                is_empty = true;
+                Ok(())
+            } else {
+                Err(meta.error("unsupported format_data attribute, expected `empty`"))
+            }
+        })?;
+    }
+    Ok(is_empty)
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::parse_quote;
+
+    use super::impl_format_data;
+
+    fn error(input: syn::DeriveInput) -> String {
+        impl_format_data(input).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn newtype_and_empty_variants() {
+        let tokens = impl_format_data(parse_quote! {
+            enum MyEnum {
+                VARCHAR(String),
+                NUMBER { val: i64 },
+                #[format_data(empty)]
+                NONE,
+            }
+        }).unwrap().to_string();
+        assert!(tokens.contains("impl :: oracle_sql_tools :: format_data :: FormatData for MyEnum"));
+        assert!(tokens.contains("MyEnum :: VARCHAR (val) => :: oracle_sql_tools :: format_data :: FormatData :: fmt_data (val)"));
+        assert!(tokens.contains("MyEnum :: NUMBER { val : val } => :: oracle_sql_tools :: format_data :: FormatData :: fmt_data (val)"));
+        assert!(tokens.contains("MyEnum :: NONE => :: oracle_sql_tools :: format_data :: FormattedData :: EMPTY"));
+    }
+
+    #[test]
+    fn unmarked_unit_variant() {
+        assert_eq!(
+            error(parse_quote! { enum MyEnum { VARCHAR(String), NONE } }), 
+            "unit variants need to be marked with #[format_data(empty)]"
+        );
+    }
+
+    #[test]
+    fn multi_field_variants() {
+        assert_eq!(
+            error(parse_quote! { enum MyEnum { PAIR(String, i64) } }), 
+            "FormatData can only be derived for variants with a single field"
+        );
+        assert_eq!(
+            error(parse_quote! { enum MyEnum { PAIR { a: String, b: i64 } } }), 
+            "FormatData can only be derived for variants with a single field"
+        );
+    }
+
+    #[test]
+    fn misplaced_or_unknown_attributes() {
+        assert_eq!(
+            error(parse_quote! { enum MyEnum { #[format_data(empty)] VARCHAR(String) } }), 
+            "#[format_data(empty)] can only be used on unit variants"
+        );
+        assert_eq!(
+            error(parse_quote! { enum MyEnum { #[format_data(null)] NONE } }), 
+            "unsupported format_data attribute, expected `empty`"
+        );
+    }
+
+    #[test]
+    fn only_enums() {
+        assert_eq!(error(parse_quote! { struct MyStruct(String); }), "FormatData can only be derived for enums");
+    }
+}
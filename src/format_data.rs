@@ -1,4 +1,7 @@
-use chrono::{NaiveDate, NaiveDateTime};
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, Utc};
+
+#[cfg(feature = "derive")]
+pub use oracle_sql_tools_derive::FormatData;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum FormattedData {
@@ -7,14 +10,39 @@ pub enum FormattedData {
     FLOAT(f64),
     DATE(NaiveDate),
     TIMESTAMP(NaiveDateTime),
+    /// A timestamp that keeps its offset from UTC
+    TIMESTAMPTZ(DateTime<FixedOffset>),
+    BINARY(Vec<u8>),
+    BOOLEAN(bool),
+    /// Needs the `decimal` feature
+    #[cfg(feature = "decimal")]
+    DECIMAL(rust_decimal::Decimal),
     EMPTY,
 }
 
 /// A trait that formats the input data to match [`FormattedData`]
 ///
-/// Already implemented for `&[u8]`, `Vec<u8>`, `&str`, 'String', 'i8', 'i16', 'i32', 'i64', 'f32', 'f64', and [`chrono::NaiveDateTime`], as well as, their Option<> variants
+/// Already implemented for `&[u8]`, `Vec<u8>`, `&str`, 'String', 'i8', 'i16', 'i32', 'i64', 'f32', 'f64', 'bool', [`chrono::NaiveDateTime`], `DateTime<Utc>` and `DateTime<FixedOffset>`, as well as, their Option<> variants
+///
+/// [`chrono::DateTime`]s are kept as [`FormattedData::TIMESTAMPTZ`] with their offset, which is inserted into a `TIMESTAMP WITH TIME ZONE` column unless it's set to [`TimeZoneStorage::WithLocalTimeZone`](crate::types::TimeZoneStorage::WithLocalTimeZone).
+/// Bytes are kept as [`FormattedData::BINARY`], which is inserted into a `RAW` or `BLOB` column.
+/// With the `decimal` feature, [`rust_decimal::Decimal`] is kept as [`FormattedData::DECIMAL`], which is inserted into a `NUMBER` column without going through a float.
+/// Booleans are kept as [`FormattedData::BOOLEAN`], which is inserted into a `BOOLEAN` column on 23ai, otherwise as set by [`BooleanStorage`](crate::types::BooleanStorage).
+///
+/// With the `derive` feature enabled, it can be derived for a local enum whose variants each wrap a type that already implements `FormatData`:
+///
+/// ```no_run
+/// #[derive(FormatData)]
+/// enum MyEnum {
+///     VARCHAR(String),
+///     NUMBER(i64),
+///     // unit variants need to be marked as empty
+///     #[format_data(empty)]
+///     NONE,
+/// }
+/// ```
 ///
-/// To implement a local enum: 
+/// To implement a local enum by hand: 
 ///
 /// ```no_run
 /// enum MyEnum {
@@ -53,51 +81,6 @@ pub enum FormattedData {
 pub trait FormatData { fn fmt_data(self) -> FormattedData; }
 
 impl FormatData for FormattedData { fn fmt_data(self) -> Self { self } }
-impl FormatData for &[u8] {
-    fn fmt_data(self) -> FormattedData {
-        let clone_on_write_string = String::from_utf8_lossy(self);
-        let utf8_string = clone_on_write_string.replace(|c: char| !c.is_ascii(), "");
-        FormattedData::STRING(utf8_string)
-    }
-}
-impl FormatData for Vec<u8> {
-    fn fmt_data(self) -> FormattedData {
-        let utf8_string = String::from_utf8(self)
-            .map_err(|non_utf8| 
-                String::from_utf8_lossy(non_utf8.as_bytes()).into_owned()
-            )
-            .unwrap();
-        FormattedData::STRING(utf8_string)
-    }
-}
-impl FormatData for Option<&[u8]> {
-    fn fmt_data(self) -> FormattedData {
-        match self {
-            Some(val) => {
-                let clone_on_write_string = String::from_utf8_lossy(val);
-                let utf8_string = clone_on_write_string.replace(|c: char| !c.is_ascii(), "");
-                FormattedData::STRING(utf8_string)
-            },
-            None => FormattedData::EMPTY,
-        }
-    }
-}
-impl FormatData for Option<Vec<u8>> {
-    fn fmt_data(self) -> FormattedData {
-        match self {
-            Some(val) => {
-                let utf8_string = String::from_utf8(val)
-                    .map_err(|non_utf8| 
-                        String::from_utf8_lossy(non_utf8.as_bytes()).into_owned()
-                    )
-                    .unwrap();
-                FormattedData::STRING(utf8_string)
-            },
-            None => FormattedData::EMPTY,
-        }
-    }
-}
-
 macro_rules! impl_fmt_data {
     ($data_type:ty, $enum_type:ident) => {
         impl FormatData for $data_type {
@@ -111,9 +94,29 @@ impl_fmt_data!(i8, INT);
 impl_fmt_data!(i16, INT);
 impl_fmt_data!(i32, INT);
 impl_fmt_data!(i64, INT);
-impl_fmt_data!(f32, FLOAT);
 impl_fmt_data!(f64, FLOAT);
 impl_fmt_data!(NaiveDateTime, TIMESTAMP);
+impl_fmt_data!(DateTime<Utc>, TIMESTAMPTZ);
+impl_fmt_data!(DateTime<FixedOffset>, TIMESTAMPTZ);
+impl_fmt_data!(&[u8], BINARY);
+impl_fmt_data!(Vec<u8>, BINARY);
+impl_fmt_data!(bool, BOOLEAN);
+#[cfg(feature = "decimal")]
+impl_fmt_data!(rust_decimal::Decimal, DECIMAL);
+
+// an `f32` cast straight to `f64` keeps its binary error (0.1 becomes 0.10000000149011612), 
+// so it's widened through its shortest text instead, which is what was written in the first place
+pub(crate) fn f32_to_f64(val: f32) -> f64 { val.to_string().parse().unwrap_or(val as f64) }
+
+impl FormatData for f32 { fn fmt_data(self) -> FormattedData { FormattedData::FLOAT(f32_to_f64(self)) } }
+impl FormatData for Option<f32> {
+    fn fmt_data(self) -> FormattedData {
+        match self {
+            Some(val) => FormattedData::FLOAT(f32_to_f64(val)),
+            None => FormattedData::EMPTY,
+        }
+    }
+}
 
 macro_rules! impl_fmt_data_option {
     ($data_type:ty, $enum_type:ident) => {
@@ -133,6 +136,26 @@ impl_fmt_data_option!(Option<i8>, INT);
 impl_fmt_data_option!(Option<i16>, INT);
 impl_fmt_data_option!(Option<i32>, INT);
 impl_fmt_data_option!(Option<i64>, INT);
-impl_fmt_data_option!(Option<f32>, FLOAT);
 impl_fmt_data_option!(Option<f64>, FLOAT);
-impl_fmt_data_option!(Option<NaiveDateTime>, TIMESTAMP);
\ No newline at end of file
+impl_fmt_data_option!(Option<NaiveDateTime>, TIMESTAMP);
+impl_fmt_data_option!(Option<DateTime<Utc>>, TIMESTAMPTZ);
+impl_fmt_data_option!(Option<DateTime<FixedOffset>>, TIMESTAMPTZ);
+impl_fmt_data_option!(Option<&[u8]>, BINARY);
+impl_fmt_data_option!(Option<Vec<u8>>, BINARY);
+impl_fmt_data_option!(Option<bool>, BOOLEAN);
+#[cfg(feature = "decimal")]
+impl_fmt_data_option!(Option<rust_decimal::Decimal>, DECIMAL);
+
+#[cfg(test)]
+mod tests {
+    use super::{FormatData, FormattedData};
+
+    #[test]
+    fn f32_keeps_its_decimal_value() {
+        assert_eq!(0.1f32.fmt_data(), FormattedData::FLOAT(0.1));
+        assert_eq!(Some(123.456f32).fmt_data(), FormattedData::FLOAT(123.456));
+        assert_eq!(None::<f32>.fmt_data(), FormattedData::EMPTY);
+        assert_eq!((-2.5e-7f32).fmt_data(), FormattedData::FLOAT(-2.5e-7));
+        assert_eq!(f32::MAX.fmt_data(), FormattedData::FLOAT(f32::MAX.to_string().parse().unwrap()));
+    }
+}
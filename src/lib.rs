@@ -1,15 +1,21 @@
 #![doc = include_str!("../README.md")]
 
+use std::sync::Arc;
 use oracle::Connection;
+use serde::Serialize;
 
-use statements::{PreppedGridData, PreppedRowData};
+use serialize::RowSerializer;
+use statements::{PreppedDelete, PreppedGridData, PreppedRowData};
 use format_data::{FormatData, FormattedData};
-use types::DatatypeIndexes;
+use progress::NoProgress;
+use types::{errors::OracleSqlToolsError, BooleanStorage, DatatypeIndexes, InsertOptions};
 
 pub mod statements;
 pub mod types;
 pub mod utils;
 pub mod format_data;
+pub mod progress;
+mod serialize;
 
 /// A trait to prepare either a vector or a 2-dimensional vector for a SQL query
 ///
@@ -50,52 +56,18 @@ impl<T: FormatData> PrepData<T> for Vec<Vec<T>> {
     type Prep = PreppedGridData;
 
     fn prep_data(self, connection: Connection) -> Self::Prep  {
-        // get's the 'dominate' datatype from each column
-        // weighted in order: VARCHAR2, FLOAT, INT, DATE
-        let mut is_varchar: Vec<usize> = Vec::new();
-        let mut is_float: Vec<usize> = Vec::new();
-        let mut is_int: Vec<usize> = Vec::new();
-        let mut is_date: Vec<usize> = Vec::new();
-
-        let mut data = Vec::new();
-        let mut y_index: usize = 0 as usize;
-        for row in self {
-            let mut inner_vec = Vec::new();
-            let mut x_index: usize = 0 as usize;
-            for cell in row {
-                let formatted_cell = cell.fmt_data();
-                if y_index > 0 {
-                    match &formatted_cell {
-                        FormattedData::STRING(_) => is_varchar.push(x_index),
-                        FormattedData::INT(_) => is_int.push(x_index),
-                        FormattedData::FLOAT(_) => is_float.push(x_index),
-                        FormattedData::DATE(_) => is_date.push(x_index),
-                        FormattedData::TIMESTAMP(_) => is_date.push(x_index),
-                        FormattedData::EMPTY => { 
-                            inner_vec.push(formatted_cell); 
-                            x_index += 1 as usize; 
-                            continue;
-                        },
-                    }
-                }
-                inner_vec.push(formatted_cell);
-                x_index += 1 as usize;
-            }
-            data.push(inner_vec);
-            y_index += 1 as usize;
-        }
-
-        let data_indexes = DatatypeIndexes {
-            is_varchar,
-            is_float,
-            is_int,
-            is_date,
-        }.find_uniques();
+        let data = self.into_iter()
+            .map(|row| row.into_iter().map(|cell| cell.fmt_data()).collect())
+            .collect::<Vec<Vec<FormattedData>>>();
+        let data_indexes = DatatypeIndexes::from_grid(&data);
 
         Self::Prep {
             data,
             conn: connection,
-            data_indexes
+            data_indexes,
+            options: InsertOptions::default(),
+            progress: Arc::new(NoProgress),
+            dialect: None,
         }
     }
 }
@@ -112,6 +84,107 @@ impl<T: FormatData> PrepData<T> for Vec<T> {
             query: None,
             header: None,
             filters: None,
+            limit: None,
+            dialect: None,
+            boolean_storage: BooleanStorage::default(),
+        }
+    }
+}
+
+/// A trait to prepare a vector of structs to be inserted into a table
+///
+/// The field names become the header row and each struct becomes a row of the grid, which is then prepared the same way as [`PrepData`] would with a 2-dimensional vector. 
+/// Fields can be renamed or skipped with the usual serde attributes.
+///
+/// ```no_run
+/// #[derive(serde::Serialize)]
+/// struct MyRecord {
+///     #[serde(rename = "Employee ID")]
+///     id: i64,
+///     name: String,
+///     salary: Option<f64>,
+/// }
+///
+/// let conn: oracle::Connection = match Connection::connect("<USERNAME>", "<PASSWORD>", "<IP ADDRESS>")?; 
+///
+/// let records: Vec<MyRecord> = vec![
+///     MyRecord { id: 1, name: "A".to_string(), salary: Some(50000.0) },
+///     MyRecord { id: 2, name: "B".to_string(), salary: None },
+/// ];
+///
+/// records.prep_structs(conn)?.insert("MY_TABLE")?;
+/// ```
+pub trait PrepStructs {
+    fn prep_structs(self, connection: Connection) -> Result<PreppedGridData, OracleSqlToolsError>;
+
+    /// Works the same as [`.prep_structs()`](PrepStructs::prep_structs), but strings in the ISO 8601 formats chrono serializes its dates to 
+    /// are stored as `DATE`, `TIMESTAMP` or `TIMESTAMP WITH TIME ZONE` instead of text
+    ///
+    /// Every string field is checked, so a text field that happens to hold a date is converted as well.
+    fn prep_structs_with_dates(self, connection: Connection) -> Result<PreppedGridData, OracleSqlToolsError>;
+}
+
+impl<S: Serialize> PrepStructs for Vec<S> {
+    fn prep_structs(self, connection: Connection) -> Result<PreppedGridData, OracleSqlToolsError> {
+        Ok(structs_to_grid(&self, false)?.prep_data(connection))
+    }
+
+    fn prep_structs_with_dates(self, connection: Connection) -> Result<PreppedGridData, OracleSqlToolsError> {
+        Ok(structs_to_grid(&self, true)?.prep_data(connection))
+    }
+}
+
+fn structs_to_grid<S: Serialize>(records: &[S], parse_dates: bool) -> Result<Vec<Vec<FormattedData>>, OracleSqlToolsError> {
+    let mut grid: Vec<Vec<FormattedData>> = Vec::new();
+    let mut header: Vec<String> = Vec::new();
+    for (row_index, record) in records.iter().enumerate() {
+        let mut serializer = RowSerializer::new(parse_dates);
+        record.serialize(&mut serializer).map_err(|e| OracleSqlToolsError::SerializeError { 
+            error_message: e.to_string(), 
+            row_index,
+        })?;
+        // the first struct's field names become the header, every other struct has to match it
+        if row_index == 0 { 
+            header = serializer.header;
+            grid.push(header.iter().map(|col| FormattedData::STRING(col.to_owned())).collect());
+        } else if serializer.header != header { 
+            return Err(OracleSqlToolsError::SerializeError { 
+                error_message: format!("fields {:?} don't match the first row's fields {:?}", serializer.header, header), 
+                row_index,
+            })
         }
+        grid.push(serializer.row);
     }
-}
\ No newline at end of file
+    if grid.len() <= 1 { return Err(OracleSqlToolsError::NoData) }
+    Ok(grid)
+}
+
+/// A trait to start a delete statement on a [`Connection`]
+///
+/// The delete borrows the connection, so the deleted rows can be committed or rolled back afterwards.
+///
+/// ```no_run
+/// let conn: oracle::Connection = match Connection::connect("<USERNAME>", "<PASSWORD>", "<IP ADDRESS>")?; 
+///
+/// let rows_deleted: u64 = conn.delete_from("MY_TABLE")
+///     .filter(ClauseType::Where, "Business Unit", "Retail")
+///     .filter_by(ClauseType::And, "Hire Date", Operator::LessThan(FormattedData::DATE(NaiveDate::from_ymd_opt(2020, 1, 1).unwrap())))
+///     .execute()?;
+/// conn.commit()?;
+/// ```
+pub trait DeleteFrom {
+    fn delete_from(&self, table_name: &str) -> PreppedDelete<'_>;
+}
+
+impl DeleteFrom for Connection {
+    fn delete_from(&self, table_name: &str) -> PreppedDelete<'_> {
+        PreppedDelete {
+            conn: self,
+            table_name: table_name.to_string(),
+            filters: Vec::new(),
+            delete_all: false,
+            dialect: None,
+            boolean_storage: BooleanStorage::default(),
+        }
+    }
+}
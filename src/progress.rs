@@ -0,0 +1,59 @@
+use std::fmt;
+
+/// Receives updates on how many rows have been written while the data is split between threads
+///
+/// Every method has an empty default, so only the ones you need have to be implemented. 
+/// The observer is shared between the threads, so it needs to be [`Send`] and [`Sync`].
+///
+/// ```no_run
+/// struct LogProgress;
+///
+/// impl ProgressObserver for LogProgress {
+///     fn rows_done(&self, thread: usize, rows: usize) {
+///         log::info!("thread {} wrote {} rows", thread, rows);
+///     }
+/// }
+///
+/// data.prep_data(conn).with_progress(LogProgress).insert("MY_TABLE")?;
+/// ```
+pub trait ProgressObserver: Send + Sync {
+    /// Called before any rows are written, with the number of rows given to each thread
+    fn start(&self, _total_rows: usize, _rows_per_thread: &[usize]) {}
+    /// Called each time a thread executes a batch, with the number of rows that were in it
+    fn rows_done(&self, _thread: usize, _rows: usize) {}
+    /// Called once every thread has finished
+    fn finish(&self) {}
+}
+
+impl fmt::Debug for dyn ProgressObserver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ProgressObserver")
+    }
+}
+
+/// The default observer, which ignores every update
+#[derive(Debug, Default)]
+pub struct NoProgress;
+
+impl ProgressObserver for NoProgress {}
+
+/// Draws the progress to the terminal, requires the `progress_bar` feature
+///
+/// The length of the bar is set when the insert starts.
+/// ```no_run
+/// data.prep_data(conn).with_progress(indicatif::ProgressBar::new(0)).insert("MY_TABLE")?;
+/// ```
+#[cfg(feature = "progress_bar")]
+impl ProgressObserver for indicatif::ProgressBar {
+    fn start(&self, total_rows: usize, _rows_per_thread: &[usize]) {
+        self.set_length(total_rows as u64);
+    }
+
+    fn rows_done(&self, _thread: usize, rows: usize) {
+        self.inc(rows as u64);
+    }
+
+    fn finish(&self) {
+        indicatif::ProgressBar::finish(self);
+    }
+}
@@ -0,0 +1,389 @@
+use std::fmt::{self, Display};
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use serde::{ser::{self, Impossible, SerializeMap, SerializeSeq, SerializeStruct}, Serialize, Serializer};
+
+use crate::format_data::{f32_to_f64, FormattedData};
+
+#[derive(Debug)]
+pub(crate) struct SerializeError(String);
+
+impl Display for SerializeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result { f.write_str(&self.0) }
+}
+
+impl std::error::Error for SerializeError {}
+
+impl ser::Error for SerializeError {
+    fn custom<T: Display>(msg: T) -> Self { SerializeError(msg.to_string()) }
+}
+
+/// Serializes a struct (or map) into its field names and a row of cells
+pub(crate) struct RowSerializer {
+    pub header: Vec<String>,
+    pub row: Vec<FormattedData>,
+    /// Stores strings in chrono's ISO 8601 formats as dates instead of text
+    parse_dates: bool,
+    next_key: Option<String>,
+}
+
+impl RowSerializer {
+    pub fn new(parse_dates: bool) -> Self {
+        RowSerializer { header: Vec::new(), row: Vec::new(), parse_dates, next_key: None }
+    }
+}
+
+macro_rules! not_a_row {
+    ($($method:ident($($arg:ty),*) -> $ret:ty;)*) => {
+        $(fn $method(self, $(_: $arg),*) -> Result<$ret, Self::Error> {
+            Err(SerializeError("only structs and maps can be inserted as rows".to_string()))
+        })*
+    };
+}
+
+impl Serializer for &mut RowSerializer {
+    type Ok = ();
+    type Error = SerializeError;
+    type SerializeSeq = Impossible<(), SerializeError>;
+    type SerializeTuple = Impossible<(), SerializeError>;
+    type SerializeTupleStruct = Impossible<(), SerializeError>;
+    type SerializeTupleVariant = Impossible<(), SerializeError>;
+    type SerializeMap = Self;
+    type SerializeStruct = Self;
+    type SerializeStructVariant = Impossible<(), SerializeError>;
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct, Self::Error> { Ok(self) }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> { Ok(self) }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(self, _name: &'static str, value: &T) -> Result<Self::Ok, Self::Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<Self::Ok, Self::Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(self, _name: &'static str, _variant_index: u32, _variant: &'static str, _value: &T) -> Result<Self::Ok, Self::Error> {
+        Err(SerializeError("only structs and maps can be inserted as rows".to_string()))
+    }
+
+    not_a_row! {
+        serialize_bool(bool) -> ();
+        serialize_i8(i8) -> ();
+        serialize_i16(i16) -> ();
+        serialize_i32(i32) -> ();
+        serialize_i64(i64) -> ();
+        serialize_u8(u8) -> ();
+        serialize_u16(u16) -> ();
+        serialize_u32(u32) -> ();
+        serialize_u64(u64) -> ();
+        serialize_f32(f32) -> ();
+        serialize_f64(f64) -> ();
+        serialize_char(char) -> ();
+        serialize_str(&str) -> ();
+        serialize_bytes(&[u8]) -> ();
+        serialize_none() -> ();
+        serialize_unit() -> ();
+        serialize_unit_struct(&'static str) -> ();
+        serialize_unit_variant(&'static str, u32, &'static str) -> ();
+        serialize_seq(Option<usize>) -> Self::SerializeSeq;
+        serialize_tuple(usize) -> Self::SerializeTuple;
+        serialize_tuple_struct(&'static str, usize) -> Self::SerializeTupleStruct;
+        serialize_tuple_variant(&'static str, u32, &'static str, usize) -> Self::SerializeTupleVariant;
+        serialize_struct_variant(&'static str, u32, &'static str, usize) -> Self::SerializeStructVariant;
+    }
+}
+
+impl SerializeStruct for &mut RowSerializer {
+    type Ok = ();
+    type Error = SerializeError;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, key: &'static str, value: &T) -> Result<(), Self::Error> {
+        self.header.push(key.to_string());
+        self.row.push(value.serialize(CellSerializer { parse_dates: self.parse_dates })?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<(), Self::Error> { Ok(()) }
+}
+
+impl SerializeMap for &mut RowSerializer {
+    type Ok = ();
+    type Error = SerializeError;
+
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<(), Self::Error> {
+        match key.serialize(CellSerializer { parse_dates: false })? {
+            FormattedData::STRING(key) => { self.next_key = Some(key); Ok(()) },
+            _ => Err(SerializeError("map keys need to be strings to be used as column names".to_string())),
+        }
+    }
+
+    fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Self::Error> {
+        match self.next_key.take() {
+            Some(key) => {
+                self.header.push(key);
+                self.row.push(value.serialize(CellSerializer { parse_dates: self.parse_dates })?);
+                Ok(())
+            },
+            None => Err(SerializeError("map value serialized before its key".to_string())),
+        }
+    }
+
+    fn end(self) -> Result<(), Self::Error> { Ok(()) }
+}
+
+/// Serializes a single field into a cell
+pub(crate) struct CellSerializer {
+    parse_dates: bool,
+}
+
+macro_rules! serialize_cell {
+    ($($method:ident($data_type:ty) => $enum_type:ident;)*) => {
+        $(fn $method(self, v: $data_type) -> Result<Self::Ok, Self::Error> { Ok(FormattedData::$enum_type(v.into())) })*
+    };
+}
+
+macro_rules! nested_value {
+    ($($method:ident($($arg:ty),*) -> $ret:ty;)*) => {
+        $(fn $method(self, $(_: $arg),*) -> Result<$ret, Self::Error> {
+            Err(SerializeError("nested values can't be stored in a single cell".to_string()))
+        })*
+    };
+}
+
+impl Serializer for CellSerializer {
+    type Ok = FormattedData;
+    type Error = SerializeError;
+    type SerializeSeq = BytesSerializer;
+    type SerializeTuple = Impossible<FormattedData, SerializeError>;
+    type SerializeTupleStruct = Impossible<FormattedData, SerializeError>;
+    type SerializeTupleVariant = Impossible<FormattedData, SerializeError>;
+    type SerializeMap = Impossible<FormattedData, SerializeError>;
+    type SerializeStruct = Impossible<FormattedData, SerializeError>;
+    type SerializeStructVariant = Impossible<FormattedData, SerializeError>;
+
+    serialize_cell! {
+        serialize_i8(i8) => INT;
+        serialize_i16(i16) => INT;
+        serialize_i32(i32) => INT;
+        serialize_i64(i64) => INT;
+        serialize_u8(u8) => INT;
+        serialize_u16(u16) => INT;
+        serialize_u32(u32) => INT;
+        serialize_f64(f64) => FLOAT;
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Self::Ok, Self::Error> { Ok(FormattedData::FLOAT(f32_to_f64(v))) }
+
+    fn serialize_bool(self, v: bool) -> Result<Self::Ok, Self::Error> { Ok(FormattedData::BOOLEAN(v)) }
+
+    fn serialize_u64(self, v: u64) -> Result<Self::Ok, Self::Error> {
+        match i64::try_from(v) {
+            Ok(val) => Ok(FormattedData::INT(val)),
+            Err(_) => Ok(FormattedData::STRING(v.to_string())),
+        }
+    }
+
+    fn serialize_char(self, v: char) -> Result<Self::Ok, Self::Error> { Ok(FormattedData::STRING(v.to_string())) }
+
+    // chrono serializes its dates as ISO 8601 strings, so they're converted back when asked to keep them as dates in the table
+    fn serialize_str(self, v: &str) -> Result<Self::Ok, Self::Error> {
+        if !self.parse_dates { return Ok(FormattedData::STRING(v.to_string())) }
+        if let Ok(val) = DateTime::parse_from_rfc3339(v) { return Ok(FormattedData::TIMESTAMPTZ(val)) }
+        if let Ok(val) = NaiveDateTime::parse_from_str(v, "%Y-%m-%dT%H:%M:%S%.f") { return Ok(FormattedData::TIMESTAMP(val)) }
+        if let Ok(val) = NaiveDate::parse_from_str(v, "%Y-%m-%d") { return Ok(FormattedData::DATE(val)) }
+        Ok(FormattedData::STRING(v.to_string()))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok, Self::Error> {
+        Ok(crate::format_data::FormatData::fmt_data(v))
+    }
+
+    fn serialize_none(self) -> Result<Self::Ok, Self::Error> { Ok(FormattedData::EMPTY) }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<Self::Ok, Self::Error> { value.serialize(self) }
+
+    fn serialize_unit(self) -> Result<Self::Ok, Self::Error> { Ok(FormattedData::EMPTY) }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Self::Ok, Self::Error> { Ok(FormattedData::EMPTY) }
+
+    // unit variants of an enum are stored by name
+    fn serialize_unit_variant(self, _name: &'static str, _variant_index: u32, variant: &'static str) -> Result<Self::Ok, Self::Error> {
+        Ok(FormattedData::STRING(variant.to_string()))
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(self, _name: &'static str, value: &T) -> Result<Self::Ok, Self::Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(self, _name: &'static str, _variant_index: u32, _variant: &'static str, value: &T) -> Result<Self::Ok, Self::Error> {
+        value.serialize(self)
+    }
+
+    // serde writes a `Vec<u8>` as a sequence, so it's collected back into bytes
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+        Ok(BytesSerializer { bytes: Vec::with_capacity(len.unwrap_or(0)) })
+    }
+
+    nested_value! {
+        serialize_tuple(usize) -> Self::SerializeTuple;
+        serialize_tuple_struct(&'static str, usize) -> Self::SerializeTupleStruct;
+        serialize_tuple_variant(&'static str, u32, &'static str, usize) -> Self::SerializeTupleVariant;
+        serialize_map(Option<usize>) -> Self::SerializeMap;
+        serialize_struct(&'static str, usize) -> Self::SerializeStruct;
+        serialize_struct_variant(&'static str, u32, &'static str, usize) -> Self::SerializeStructVariant;
+    }
+}
+
+/// Collects a sequence of `u8` into a single binary cell
+pub(crate) struct BytesSerializer {
+    bytes: Vec<u8>,
+}
+
+impl SerializeSeq for BytesSerializer {
+    type Ok = FormattedData;
+    type Error = SerializeError;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Self::Error> {
+        self.bytes.push(value.serialize(ByteSerializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> { Ok(FormattedData::BINARY(self.bytes)) }
+}
+
+/// Serializes one element of a byte sequence, anything other than a `u8` is a nested value
+struct ByteSerializer;
+
+impl Serializer for ByteSerializer {
+    type Ok = u8;
+    type Error = SerializeError;
+    type SerializeSeq = Impossible<u8, SerializeError>;
+    type SerializeTuple = Impossible<u8, SerializeError>;
+    type SerializeTupleStruct = Impossible<u8, SerializeError>;
+    type SerializeTupleVariant = Impossible<u8, SerializeError>;
+    type SerializeMap = Impossible<u8, SerializeError>;
+    type SerializeStruct = Impossible<u8, SerializeError>;
+    type SerializeStructVariant = Impossible<u8, SerializeError>;
+
+    fn serialize_u8(self, v: u8) -> Result<Self::Ok, Self::Error> { Ok(v) }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, _value: &T) -> Result<Self::Ok, Self::Error> {
+        Err(SerializeError("nested values can't be stored in a single cell".to_string()))
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(self, _name: &'static str, _value: &T) -> Result<Self::Ok, Self::Error> {
+        Err(SerializeError("nested values can't be stored in a single cell".to_string()))
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(self, _name: &'static str, _variant_index: u32, _variant: &'static str, _value: &T) -> Result<Self::Ok, Self::Error> {
+        Err(SerializeError("nested values can't be stored in a single cell".to_string()))
+    }
+
+    nested_value! {
+        serialize_bool(bool) -> u8;
+        serialize_i8(i8) -> u8;
+        serialize_i16(i16) -> u8;
+        serialize_i32(i32) -> u8;
+        serialize_i64(i64) -> u8;
+        serialize_u16(u16) -> u8;
+        serialize_u32(u32) -> u8;
+        serialize_u64(u64) -> u8;
+        serialize_f32(f32) -> u8;
+        serialize_f64(f64) -> u8;
+        serialize_char(char) -> u8;
+        serialize_str(&str) -> u8;
+        serialize_bytes(&[u8]) -> u8;
+        serialize_none() -> u8;
+        serialize_unit() -> u8;
+        serialize_unit_struct(&'static str) -> u8;
+        serialize_unit_variant(&'static str, u32, &'static str) -> u8;
+        serialize_seq(Option<usize>) -> Self::SerializeSeq;
+        serialize_tuple(usize) -> Self::SerializeTuple;
+        serialize_tuple_struct(&'static str, usize) -> Self::SerializeTupleStruct;
+        serialize_tuple_variant(&'static str, u32, &'static str, usize) -> Self::SerializeTupleVariant;
+        serialize_map(Option<usize>) -> Self::SerializeMap;
+        serialize_struct(&'static str, usize) -> Self::SerializeStruct;
+        serialize_struct_variant(&'static str, u32, &'static str, usize) -> Self::SerializeStructVariant;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, NaiveDateTime};
+    use serde::Serialize;
+
+    use crate::format_data::FormattedData;
+    use super::RowSerializer;
+
+    #[derive(Serialize)]
+    struct Upload {
+        id: i64,
+        name: String,
+        note: Option<String>,
+        created: NaiveDateTime,
+        due: NaiveDate,
+        data: Vec<u8>,
+    }
+
+    fn upload() -> Upload {
+        Upload {
+            id: 1,
+            name: "report".to_string(),
+            note: None,
+            created: NaiveDate::from_ymd_opt(2024, 1, 31).unwrap().and_hms_opt(8, 30, 0).unwrap(),
+            due: NaiveDate::from_ymd_opt(2024, 2, 1).unwrap(),
+            data: vec![0, 1, 255],
+        }
+    }
+
+    fn serialize(parse_dates: bool) -> RowSerializer {
+        let mut serializer = RowSerializer::new(parse_dates);
+        upload().serialize(&mut serializer).unwrap();
+        serializer
+    }
+
+    #[test]
+    fn fields_keep_their_declared_order() {
+        let serializer = serialize(false);
+        assert_eq!(serializer.header, vec!["id", "name", "note", "created", "due", "data"]);
+        assert_eq!(serializer.row.len(), serializer.header.len());
+        assert_eq!(serializer.row[0], FormattedData::INT(1));
+        assert_eq!(serializer.row[1], FormattedData::STRING("report".to_string()));
+    }
+
+    #[test]
+    fn none_is_empty() {
+        assert_eq!(serialize(false).row[2], FormattedData::EMPTY);
+    }
+
+    #[test]
+    fn strings_stay_text_without_date_parsing() {
+        let row = serialize(false).row;
+        assert_eq!(row[3], FormattedData::STRING("2024-01-31T08:30:00".to_string()));
+        assert_eq!(row[4], FormattedData::STRING("2024-02-01".to_string()));
+    }
+
+    #[test]
+    fn date_strings_parse_when_asked() {
+        let upload = upload();
+        let row = serialize(true).row;
+        assert_eq!(row[1], FormattedData::STRING("report".to_string()));
+        assert_eq!(row[3], FormattedData::TIMESTAMP(upload.created));
+        assert_eq!(row[4], FormattedData::DATE(upload.due));
+    }
+
+    #[test]
+    fn byte_vectors_are_binary() {
+        assert_eq!(serialize(false).row[5], FormattedData::BINARY(vec![0, 1, 255]));
+    }
+
+    #[test]
+    fn f32_fields_keep_their_decimal_value() {
+        #[derive(Serialize)]
+        struct Reading { value: f32 }
+        let mut serializer = RowSerializer::new(false);
+        Reading { value: 0.1 }.serialize(&mut serializer).unwrap();
+        assert_eq!(serializer.row, vec![FormattedData::FLOAT(0.1)]);
+    }
+}
@@ -1,12 +1,19 @@
 use std::collections::HashMap;
 use oracle::Connection;
 
-use crate::{format_data::FormattedData, types::{errors::OracleSqlToolsError, DatatypeIndexes}};
+use crate::{format_data::FormattedData, types::{errors::OracleSqlToolsError, DatatypeIndexes, Dialect, InsertOptions}};
+use crate::utils::to_hex;
 use super::{mutate_row::MutateRow, utils::remove_invalid_chars};
 
 pub(crate) trait CreateFromInsert {
-    fn create_table(&self, table_name: &str, col_indexes: &DatatypeIndexes, conn: &Connection) 
-    -> Result<(), OracleSqlToolsError>;
+    fn varchar_sizes(&self, col_indexes: &DatatypeIndexes) -> HashMap<usize, usize>;
+    fn binary_sizes(&self, col_indexes: &DatatypeIndexes) -> HashMap<usize, usize>;
+    fn decimal_sizes(&self, col_indexes: &DatatypeIndexes) -> HashMap<usize, (usize, usize)>;
+    fn column_types(&self, col_indexes: &DatatypeIndexes, dialect: Dialect, options: &InsertOptions) -> Vec<String>;
+    fn create_table_stmt(&self, table_name: &str, col_indexes: &DatatypeIndexes, dialect: Dialect, options: &InsertOptions) 
+    -> Result<String, OracleSqlToolsError>;
+    fn create_table(&self, table_name: &str, col_indexes: &DatatypeIndexes, dialect: Dialect, options: &InsertOptions, conn: &Connection) 
+    -> Result<String, OracleSqlToolsError>;
 }
 
 macro_rules! compare_data_length {
@@ -22,10 +29,7 @@ macro_rules! compare_data_length {
 }
 
 impl CreateFromInsert for Vec<Vec<FormattedData>> {
-    fn create_table(&self, table_name: &str, data_type_indexes: &DatatypeIndexes, conn: &Connection) 
-    -> Result<(), OracleSqlToolsError> {
-        if self.len() <= 1 { return Err(OracleSqlToolsError::NoData); }
-
+    fn varchar_sizes(&self, data_type_indexes: &DatatypeIndexes) -> HashMap<usize, usize> {
         let mut varchar_col_size: HashMap<usize, usize> = HashMap::new();
         for x in 0..self[0].len() {
             if !data_type_indexes.is_varchar.contains(&x) { continue; };
@@ -36,31 +40,200 @@ impl CreateFromInsert for Vec<Vec<FormattedData>> {
                     FormattedData::FLOAT(val) => compare_data_length!(varchar_col_size, val, x),
                     FormattedData::DATE(val) => compare_data_length!(varchar_col_size, val, x),
                     FormattedData::TIMESTAMP(val) => compare_data_length!(varchar_col_size, val, x),
+                    FormattedData::TIMESTAMPTZ(val) => compare_data_length!(varchar_col_size, val, x),
+                    FormattedData::BINARY(val) => { let val = to_hex(val); compare_data_length!(varchar_col_size, val, x) },
+                    FormattedData::BOOLEAN(val) => compare_data_length!(varchar_col_size, val, x),
+                    #[cfg(feature = "decimal")]
+                    FormattedData::DECIMAL(val) => compare_data_length!(varchar_col_size, val, x),
                     FormattedData::EMPTY => { let val = 0 as usize; compare_data_length!(varchar_col_size, val, x) },
                 };
             }
         }
+        varchar_col_size
+    }
+
+    fn binary_sizes(&self, data_type_indexes: &DatatypeIndexes) -> HashMap<usize, usize> {
+        let mut binary_col_size: HashMap<usize, usize> = HashMap::new();
+        for x in data_type_indexes.is_binary.iter() {
+            let longest = self.iter().skip(1).filter_map(|row| match row.get(*x) {
+                Some(FormattedData::BINARY(val)) => Some(val.len()),
+                _ => None,
+            }).max();
+            binary_col_size.insert(*x, longest.unwrap_or(0));
+        }
+        binary_col_size
+    }
+
+    /// The most digits before and after the decimal point in each decimal column, as the precision and scale
+    fn decimal_sizes(&self, data_type_indexes: &DatatypeIndexes) -> HashMap<usize, (usize, usize)> {
+        let mut decimal_col_size: HashMap<usize, (usize, usize)> = HashMap::new();
+        for x in data_type_indexes.is_decimal.iter() {
+            let (mut integer_digits, mut scale) = (0, 0);
+            for row in self.iter().skip(1) {
+                let (int_part, frac_digits) = match row.get(*x) {
+                    #[cfg(feature = "decimal")]
+                    Some(FormattedData::DECIMAL(val)) => (val.trunc().abs().to_string(), val.scale() as usize),
+                    Some(FormattedData::INT(val)) => (val.unsigned_abs().to_string(), 0),
+                    _ => continue,
+                };
+                // a leading zero isn't stored, so `0.5` fits in `NUMBER(1,1)`
+                let digits = match int_part.as_str() {
+                    "0" => 0,
+                    _ => int_part.len(),
+                };
+                integer_digits = integer_digits.max(digits);
+                scale = scale.max(frac_digits);
+            }
+            decimal_col_size.insert(*x, (integer_digits + scale, scale));
+        }
+        decimal_col_size
+    }
 
+    fn column_types(&self, data_type_indexes: &DatatypeIndexes, dialect: Dialect, options: &InsertOptions) -> Vec<String> {
+        let varchar_col_size = self.varchar_sizes(data_type_indexes);
+        let binary_col_size = self.binary_sizes(data_type_indexes);
+        let decimal_col_size = self.decimal_sizes(data_type_indexes);
         let mut sql_data_types = Vec::new();
         for x in 0..self[0].len() {
             if data_type_indexes.is_varchar.contains(&x) {
+                // anything too long for a VARCHAR2 column goes in a CLOB
                 match varchar_col_size.get(&x) {
+                    Some(val) if *val > dialect.max_varchar2_length(options.extended_strings) => sql_data_types.push("CLOB".to_string()),
                     Some(val) => sql_data_types.push(format!("VARCHAR2({})", val)),
                     None => continue,
                 }
             } else if data_type_indexes.is_int.contains(&x) { sql_data_types.push(format!("NUMBER")) }
             else if data_type_indexes.is_float.contains(&x) { sql_data_types.push(format!("FLOAT")) }
             else if data_type_indexes.is_date.contains(&x) { sql_data_types.push(format!("DATE")) }
+            else if data_type_indexes.is_timestamp_tz.contains(&x) { sql_data_types.push(options.time_zone_storage.column_type().to_string()) }
+            else if data_type_indexes.is_decimal.contains(&x) {
+                // NUMBER can't be given a precision over 38 digits
+                match decimal_col_size.get(&x) {
+                    Some((precision, scale)) if (1..=38).contains(precision) => sql_data_types.push(format!("NUMBER({},{})", precision, scale)),
+                    _ => sql_data_types.push("NUMBER".to_string()),
+                }
+            }
+            else if data_type_indexes.is_bool.contains(&x) {
+                match dialect.supports_boolean() {
+                    true => sql_data_types.push("BOOLEAN".to_string()),
+                    false => sql_data_types.push(options.boolean_storage.column_type().to_string()),
+                }
+            }
+            else if data_type_indexes.is_binary.contains(&x) {
+                // anything too long for a RAW column goes in a BLOB
+                match binary_col_size.get(&x) {
+                    Some(val) if *val > dialect.max_raw_length(options.extended_strings) => sql_data_types.push("BLOB".to_string()),
+                    Some(val) => sql_data_types.push(format!("RAW({})", val.max(&1))),
+                    None => sql_data_types.push("BLOB".to_string()),
+                }
+            }
             else { sql_data_types.push(format!("VARCHAR2(50)")) }
         }
+        sql_data_types
+    }
 
+    fn create_table_stmt(&self, table_name: &str, data_type_indexes: &DatatypeIndexes, dialect: Dialect, options: &InsertOptions) 
+    -> Result<String, OracleSqlToolsError> {
+        if self.len() <= 1 { return Err(OracleSqlToolsError::NoData); }
+
+        let header = self[0].to_string();
+        let sql_data_types = self.column_types(data_type_indexes, dialect, options);
         let mut col_names = Vec::new();
-        for (i, col_header) in self[0].to_string().iter().enumerate() {
-            col_names.push(format!("{} {}", remove_invalid_chars(col_header), sql_data_types[i]))
+        if let Some(identity_column) = &options.identity_column {
+            if !dialect.supports_identity() {
+                return Err(OracleSqlToolsError::UnsupportedByDialect { 
+                    feature: "Identity columns".to_string(), 
+                    dialect, 
+                })
+            }
+            col_names.push(format!("{} NUMBER GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY", remove_invalid_chars(&identity_column.to_string())))
         }
-        let create_table_stmt = format!("CREATE TABLE {} ({})", table_name, col_names.join(", "));
+        for (i, col_header) in header.iter().enumerate() {
+            let col_name = remove_invalid_chars(col_header);
+            match data_type_indexes.is_bool.contains(&i) && !dialect.supports_boolean() {
+                true => col_names.push(format!("{} {} {}", col_name, sql_data_types[i], options.boolean_storage.check_constraint(&col_name))),
+                false => col_names.push(format!("{} {}", col_name, sql_data_types[i])),
+            }
+        }
+        Ok(format!("CREATE TABLE {} ({})", table_name, col_names.join(", ")))
+    }
+
+    fn create_table(&self, table_name: &str, data_type_indexes: &DatatypeIndexes, dialect: Dialect, options: &InsertOptions, conn: &Connection) 
+    -> Result<String, OracleSqlToolsError> {
+        let create_table_stmt = self.create_table_stmt(table_name, data_type_indexes, dialect, options)?;
         conn.execute(&create_table_stmt, &[])?;
         conn.commit()?;
-        Ok(())
+        Ok(create_table_stmt)
+    }
+}
+#[cfg(test)]
+mod tests {
+    use crate::{format_data::FormattedData, types::{DatatypeIndexes, Dialect, InsertOptions}};
+    use super::CreateFromInsert;
+
+    fn binary_column(len: usize) -> Vec<Vec<FormattedData>> {
+        vec![
+            vec![FormattedData::STRING("data".to_string())],
+            vec![FormattedData::BINARY(vec![0; 1])],
+            vec![FormattedData::BINARY(vec![0; len])],
+        ]
+    }
+
+    fn column_type(grid: Vec<Vec<FormattedData>>, dialect: Dialect, extended_strings: bool) -> String {
+        let options = InsertOptions { extended_strings, ..Default::default() };
+        grid.column_types(&DatatypeIndexes::from_grid(&grid), dialect, &options).remove(0)
+    }
+
+    #[test]
+    fn raw_up_to_2000_bytes() {
+        assert_eq!(column_type(binary_column(2000), Dialect::Oracle19c, false), "RAW(2000)");
+        assert_eq!(column_type(binary_column(2001), Dialect::Oracle19c, false), "BLOB");
+        assert_eq!(column_type(binary_column(0), Dialect::Oracle19c, false), "RAW(1)");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn raw_up_to_32767_bytes_with_extended_strings() {
+        assert_eq!(column_type(binary_column(2001), Dialect::Oracle19c, true), "RAW(2001)");
+        assert_eq!(column_type(binary_column(32767), Dialect::Oracle19c, true), "RAW(32767)");
+        assert_eq!(column_type(binary_column(32768), Dialect::Oracle19c, true), "BLOB");
+        // 11g never has extended strings
+        assert_eq!(column_type(binary_column(2001), Dialect::Oracle11g, true), "BLOB");
+    }
+
+    #[cfg(feature = "decimal")]
+    fn decimal_size(values: &[&str]) -> (usize, usize) {
+        let mut grid = vec![vec![FormattedData::STRING("amount".to_string())]];
+        grid.extend(values.iter().map(|val| vec![FormattedData::DECIMAL(val.parse().unwrap())]));
+        grid.decimal_sizes(&DatatypeIndexes::from_grid(&grid))[&0]
+    }
+
+    #[cfg(feature = "decimal")]
+    #[test]
+    fn decimal_precision_and_scale() {
+        assert_eq!(decimal_size(&["123.45"]), (5, 2));
+        assert_eq!(decimal_size(&["0.5"]), (1, 1));
+        // the sign isn't a digit
+        assert_eq!(decimal_size(&["-123.45", "-0.001"]), (6, 3));
+        assert_eq!(decimal_size(&["42", "-7"]), (2, 0));
+    }
+
+    #[cfg(feature = "decimal")]
+    #[test]
+    fn decimal_columns_fit_their_widest_scale_and_integer_part() {
+        // the integer digits and the scale can come from different rows
+        assert_eq!(decimal_size(&["12345.1", "0.123456", "1.5"]), (11, 6));
+        assert_eq!(decimal_size(&["1.10"]), (3, 2));
+    }
+
+    #[cfg(feature = "decimal")]
+    #[test]
+    fn decimal_columns_past_38_digits_are_plain_numbers() {
+        let grid = vec![
+            vec![FormattedData::STRING("small".to_string()), FormattedData::STRING("wide".to_string())],
+            vec![FormattedData::DECIMAL("-1.5".parse().unwrap()), FormattedData::DECIMAL("12345678901234567890.123".parse().unwrap())],
+            vec![FormattedData::DECIMAL("10".parse().unwrap()), FormattedData::DECIMAL("0.000000000000000000000000001".parse().unwrap())],
+        ];
+        let types = grid.column_types(&DatatypeIndexes::from_grid(&grid), Dialect::Oracle19c, &InsertOptions::default());
+        assert_eq!(types, vec!["NUMBER(3,1)", "NUMBER"]);
+    }
+}
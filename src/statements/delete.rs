@@ -0,0 +1,82 @@
+use oracle::sql_type::ToSql;
+
+use crate::{format_data::FormatData, types::{errors::OracleSqlToolsError, BooleanStorage, ClauseType, Condition, Dialect, Filter, Operator}};
+use super::{select::utils::{filters_column, get_filter_clauses, stored_bind_values}, PreppedDelete};
+
+impl PreppedDelete<'_> {
+    /// Adds a condition to the delete, e.g. `WHERE column = :1`
+    ///
+    /// Works the same as [`PreppedRowData::filter()`](crate::statements::PreppedRowData::filter).
+    /// ```no_run
+    /// let rows_deleted: u64 = conn.delete_from("MY_TABLE")
+    ///     .filter(ClauseType::Where, "Department", "Sales")
+    ///     .execute()?;
+    /// ```
+    pub fn filter<T: FormatData>(self, clause: ClauseType, column: &str, value: T) -> Self {
+        self.filter_by(clause, column, Operator::Equal(value.fmt_data()))
+    }
+
+    /// Adds a condition to the delete using any of the comparisons in [`Operator`]
+    pub fn filter_by(self, clause: ClauseType, column: &str, operator: Operator) -> Self {
+        self.filter_condition(clause, Condition::new(column, operator))
+    }
+
+    /// Adds a [`Condition`] to the delete, which can group and nest other conditions
+    pub fn filter_condition(mut self, clause: ClauseType, condition: Condition) -> Self {
+        self.filters.push(Filter { clause, condition });
+        self
+    }
+
+    /// Allows the delete to run without any filters, removing every row in the table
+    ///
+    /// Any filters that are added are still applied.
+    /// ```no_run
+    /// let rows_deleted: u64 = conn.delete_from("MY_TABLE").delete_all().execute()?;
+    /// ```
+    pub fn delete_all(mut self) -> Self {
+        self.delete_all = true;
+        self
+    }
+
+    /// Sets the Oracle version the delete is written for, instead of detecting it from the connection
+    ///
+    /// Only needed to decide how boolean filter values are bound, see [`.with_boolean_storage()`](PreppedDelete::with_boolean_storage).
+    pub fn with_dialect(mut self, dialect: Dialect) -> Self {
+        self.dialect = Some(dialect);
+        self
+    }
+
+    /// Sets how booleans are stored in the table, so a boolean filter value is compared the same way it was inserted
+    ///
+    /// Only used before 23ai, which has a `BOOLEAN` type. Defaults to [`BooleanStorage::Number`].
+    /// ```no_run
+    /// let rows_deleted: u64 = conn.delete_from("MY_TABLE")
+    ///     .with_boolean_storage(BooleanStorage::YesNo)
+    ///     .filter(ClauseType::Where, "Active", false)
+    ///     .execute()?;
+    /// ```
+    pub fn with_boolean_storage(mut self, boolean_storage: BooleanStorage) -> Self {
+        self.boolean_storage = boolean_storage;
+        self
+    }
+
+    /// Executes the delete and returns the number of rows that were deleted
+    ///
+    /// Returns [`OracleSqlToolsError::NoDeleteCondition`] if none of the filters compare a column and [`.delete_all()`](PreppedDelete::delete_all) wasn't called, 
+    /// e.g. an empty `Operator::NotIn` or `Condition::And` would otherwise delete every row. 
+    /// The delete isn't committed, that's left to the [`Connection`](oracle::Connection).
+    pub fn execute(self) -> Result<u64, OracleSqlToolsError> {
+        if !self.delete_all && !filters_column(&self.filters) { 
+            return Err(OracleSqlToolsError::NoDeleteCondition) 
+        }
+        let mut sql = format!("DELETE FROM {}", self.table_name);
+        let mut bind_values = Vec::new();
+        if !self.filters.is_empty() {
+            let (clauses, values) = get_filter_clauses(&self.filters);
+            sql = format!("{} {}", sql, clauses);
+            bind_values = stored_bind_values(values, self.boolean_storage, || Ok(Dialect::resolve(self.dialect, self.conn)?.supports_boolean()))?;
+        }
+        let stmt = self.conn.execute(&sql, &bind_values.iter().map(|value| value as &dyn ToSql).collect::<Vec<&dyn ToSql>>())?;
+        Ok(stmt.row_count()?)
+    }
+}
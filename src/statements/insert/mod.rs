@@ -1,15 +1,53 @@
 use std::sync::Arc;
-use oracle::Connection;
+use oracle::{pool::Pool, Connection};
 
-use crate::types::{errors::OracleSqlToolsError, BatchPrep};
-use super::{create_table::CreateFromInsert, mutate_grid::MutateGrid, mutate_row::MutateRow, utils::does_table_exist, PreppedGridData};
+use crate::{format_data::FormattedData, progress::ProgressObserver, types::{errors::OracleSqlToolsError, BatchPrep, Dialect, DryRunReport, InsertOptions, InsertReport}};
+use super::{create_table::CreateFromInsert, mutate_grid::MutateGrid, mutate_row::MutateRow, utils::{does_table_exist, has_extended_strings, remove_invalid_chars}, PreppedGridData};
 
-mod utils;
+pub(crate) mod utils;
 
 impl PreppedGridData {
+    /// Sets the number of threads and the batch size used by the insert methods
+    ///
+    /// See [`InsertOptions`] for the defaults.
+    /// ```no_run
+    /// let res: Arc<Connection> = data.prep_data(conn)
+    ///     .with_options(InsertOptions { threads: 4, batch_size: 50_000, ..Default::default() })
+    ///     .insert("MY_TABLE")?;
+    /// ```
+    pub fn with_options(mut self, options: InsertOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Sets the Oracle version the generated SQL is written for, instead of detecting it from the connection
+    ///
+    /// See [`Dialect`] for what changes between versions.
+    /// ```no_run
+    /// let res: Arc<Connection> = data.prep_data(conn).with_dialect(Dialect::Oracle11g).insert("MY_TABLE")?;
+    /// ```
+    pub fn with_dialect(mut self, dialect: Dialect) -> Self {
+        self.dialect = Some(dialect);
+        self
+    }
+
+    /// Sets the [`ProgressObserver`] that's updated as the rows are written, by default nothing is reported
+    ///
+    /// With the `progress_bar` feature enabled, an [`indicatif::ProgressBar`] can be passed in to draw the progress to the terminal.
+    /// ```no_run
+    /// let res: Arc<Connection> = data.prep_data(conn)
+    ///     .with_progress(indicatif::ProgressBar::new(0))
+    ///     .insert("MY_TABLE")?;
+    /// ```
+    pub fn with_progress<P: ProgressObserver + 'static>(mut self, progress: P) -> Self {
+        self.progress = Arc::new(progress);
+        self
+    }
+
     /// Inserts the input data into a table
     ///
-    /// Splits the data by the number of CPU threads in the host machine. Each thread creates it's own [`oracle::Batch`] which helps the upload speed for large datasets.
+    /// Splits the data by the number of CPU threads in the host machine (or [`InsertOptions::threads`]). Each thread creates it's own [`oracle::Batch`] which helps the upload speed for large datasets. 
+    /// The Batch is executed every [`InsertOptions::batch_size`] rows, so only that many rows are bound to it at a time.
     /// 
     /// # Usage
     /// 
@@ -31,8 +69,86 @@ impl PreppedGridData {
     /// Ok(())
     /// ```
     /// 'res' is Atomically Referencing 'conn' because [`Connection`](oracle::Connection) cannot be copied, and it causes a lifetime conflict with the spawned threads when borrowed normally.
+    /// 
+    /// If [`InsertOptions::batch_errors`] is enabled and the database rejects any rows, this returns [`OracleSqlToolsError::RowsRejected`]. 
+    /// Use [`.insert_with_report()`](`crate::statements::insert::PreppedGridData::insert_with_report`) to keep the good rows and see which ones were rejected.
     pub fn insert(self, table_name: &str) -> Result<Arc<Connection>, OracleSqlToolsError> {
-        stage_insert_data(self, table_name)?.split_batch_by_threads()
+        without_rejected_rows(self.insert_with_report(table_name)?)
+    }
+
+    /// Inserts the input data into a table, returning an [`InsertReport`] along with the connection
+    ///
+    /// Works the same as [`.insert()`](`crate::statements::insert::PreppedGridData::insert`), and with [`InsertOptions::batch_errors`] enabled, 
+    /// any rows the database rejects are collected into the report while the rest of the rows are still inserted. 
+    /// 
+    /// The report also has how long each thread took, whether the table was created, the statements that were run and the datatype chosen for each column, to log or audit the load.
+    /// ```no_run
+    /// let (res, report): (Arc<Connection>, InsertReport) = data.prep_data(conn)
+    ///     .with_options(InsertOptions { batch_errors: true, ..Default::default() })
+    ///     .insert_with_report("MY_TABLE")?;
+    /// for rejected in report.rejected_rows.iter() {
+    ///     println!("Row {} was rejected: {}", rejected.row_index, rejected.error);
+    /// }
+    /// res.commit()?;
+    /// ```
+    pub fn insert_with_report(self, table_name: &str) -> Result<(Arc<Connection>, InsertReport), OracleSqlToolsError> {
+        stage_insert_data(self, table_name, |header| Ok(header.insert_stmt(table_name)))?.split_batch_by_threads()
+    }
+
+    /// Inserts the input data into a table and commits it, rolling everything back if any of the threads fail
+    ///
+    /// Works the same as [`.insert_with_report()`](`crate::statements::insert::PreppedGridData::insert_with_report`), except the rows are only committed once every thread has succeeded. 
+    /// If any of them fail, the rows are rolled back and [`OracleSqlToolsError::TransactionRolledBack`] is returned with the error that caused it. 
+    /// 
+    /// For very large loads, set [`InsertOptions::commit_every`] to commit as the rows are inserted. Only the rows since the last commit can be rolled back, 
+    /// so [`InsertReport::rows_committed`] (or `rows_committed` in the error) tells you how many rows made it into the table.
+    /// ```no_run
+    /// let (res, report): (Arc<Connection>, InsertReport) = data.prep_data(conn)
+    ///     .with_options(InsertOptions { commit_every: Some(100_000), ..Default::default() })
+    ///     .insert_in_transaction("MY_TABLE")?;
+    /// println!("{} rows committed", report.rows_committed);
+    /// ```
+    pub fn insert_in_transaction(self, table_name: &str) -> Result<(Arc<Connection>, InsertReport), OracleSqlToolsError> {
+        stage_insert_data(self, table_name, |header| Ok(header.insert_stmt(table_name)))?.split_batch_in_transaction()
+    }
+
+    /// Returns the statements an insert would run and the datatype inferred for each column, without inserting anything
+    ///
+    /// The only queries run are the lookups for whether the table exists and, if it does, its column names. 
+    /// If the table doesn't exist, [`DryRunReport::create_table_stmt`] has the `CREATE TABLE` statement [`.insert()`](`crate::statements::insert::PreppedGridData::insert`) would run first.
+    /// ```no_run
+    /// let dry_run: DryRunReport = data.prep_data(conn).dry_run("MY_TABLE")?;
+    /// if let Some(ddl) = &dry_run.create_table_stmt { println!("{}", ddl); }
+    /// println!("{}", dry_run.insert_stmt);
+    /// for (column, oracle_type) in dry_run.column_types.iter() {
+    ///     println!("{}: {}", column, oracle_type);
+    /// }
+    /// ```
+    pub fn dry_run(&self, table_name: &str) -> Result<DryRunReport, OracleSqlToolsError> {
+        if self.data.len() <= 1 { return Err(OracleSqlToolsError::NoData) }
+
+        let dialect = Dialect::resolve(self.dialect, &self.conn)?;
+        let mut options = self.options.clone();
+        if !options.extended_strings {
+            options.extended_strings = has_extended_strings(&self.conn);
+        }
+        let table_exists = does_table_exist(&self.conn, table_name)?;
+        let (data_header, create_table_stmt) = match table_exists {
+            // only the header is copied to look up the table's column names the same way the insert does
+            true => {
+                let (data_header, _) = vec![self.data[0].to_owned()].replace_header(&self.conn, table_name)?;
+                (data_header, None)
+            },
+            false => (self.data[0].to_owned(), Some(self.data.create_table_stmt(table_name, &self.data_indexes, dialect, &options)?)),
+        };
+
+        Ok(DryRunReport {
+            dialect,
+            table_exists,
+            create_table_stmt,
+            insert_stmt: data_header.insert_stmt(table_name),
+            column_types: named_column_types(self, dialect, &options),
+        })
     }
 
     /// Inserts the input data into a table using only a single thread
@@ -43,14 +159,66 @@ impl PreppedGridData {
     /// ```no_run
     /// let res: Arc<Connection> = data.prep_data(conn).insert_single_thread("MY_TABLE")?;
     /// ```
-    pub fn insert_single_thread(self, table_name: &str) -> Result<Arc<Connection>, OracleSqlToolsError> {
-        stage_insert_data(self, table_name)?.single_thread_batch()
+    pub fn insert_single_thread(mut self, table_name: &str) -> Result<Arc<Connection>, OracleSqlToolsError> {
+        self.options.threads = 1;
+        without_rejected_rows(stage_insert_data(self, table_name, |header| Ok(header.insert_stmt(table_name)))?.split_batch_by_threads()?)
+    }
+
+    /// Inserts the input data into a table, giving each thread its own session from a [`Pool`]
+    ///
+    /// [`.insert()`](`crate::statements::insert::PreppedGridData::insert`) shares one [`Connection`](oracle::Connection) between all of its threads, so the database executes their batches one at a time. 
+    /// Here each thread takes a separate session from the pool so the batches run in parallel. 
+    /// 
+    /// The connection passed to [`.prep_data()`](crate::PrepData::prep_data) is only used to look up or create the table, and is released before the threads take their sessions. 
+    /// Once every thread has finished, the inserted rows are committed on every session, or rolled back on every session if any of the threads failed. 
+    /// If a commit fails, the sessions that haven't committed yet are rolled back, and [`OracleSqlToolsError::TransactionRolledBack`] has the number of rows that were committed. 
+    /// Rows rejected while [`InsertOptions::batch_errors`] is enabled don't count as a failure, they're listed in the returned [`InsertReport`]. 
+    /// 
+    /// If [`InsertOptions::commit_every`] is set, each session also commits its own rows as it goes, and a failure returns [`OracleSqlToolsError::TransactionRolledBack`] with the number of rows that were already committed.
+    /// ```no_run
+    /// let pool: Pool = PoolBuilder::new("<USERNAME>", "<PASSWORD>", "<IP ADDRESS>")
+    ///     .max_connections(num_cpus::get() as u32)
+    ///     .build()?;
+    /// let conn: oracle::Connection = pool.get()?;
+    ///
+    /// data.prep_data(conn).insert_with_pool("MY_TABLE", &pool)?;
+    /// ```
+    /// The pool needs to be able to hand out a session for each thread (see [`InsertOptions::threads`]) on top of any sessions held elsewhere, 
+    /// or acquiring them fails with `ORA-24418`. The connection passed to `.prep_data()` doesn't count, it's released first.
+    pub fn insert_with_pool(self, table_name: &str, pool: &Pool) -> Result<InsertReport, OracleSqlToolsError> {
+        stage_insert_data(self, table_name, |header| Ok(header.insert_stmt(table_name)))?.split_batch_by_pool(pool)
+    }
+}
+
+pub(crate) fn without_rejected_rows((conn, report): (Arc<Connection>, InsertReport)) -> Result<Arc<Connection>, OracleSqlToolsError> {
+    match report.rejected_rows.len() {
+        0 => Ok(conn),
+        rows_rejected => Err(OracleSqlToolsError::RowsRejected { rows_rejected }),
     }
 }
 
-fn stage_insert_data(mut grid_data: PreppedGridData, table_name: &str) -> Result<BatchPrep, OracleSqlToolsError> {
+/// Pairs each column name in the header with the Oracle datatype inferred for it
+fn named_column_types(grid_data: &PreppedGridData, dialect: Dialect, options: &InsertOptions) -> Vec<(String, String)> {
+    let column_names = grid_data.data.first().map_or(Vec::new(), |header| {
+        header.to_string().iter().map(remove_invalid_chars).collect()
+    });
+    column_names.into_iter().zip(grid_data.data.column_types(&grid_data.data_indexes, dialect, options)).collect()
+}
+
+/// Creates the table if needed and separates the header, which `build_stmt` uses to write the statement each row is bound to
+pub(crate) fn stage_insert_data<F>(mut grid_data: PreppedGridData, table_name: &str, build_stmt: F) -> Result<BatchPrep, OracleSqlToolsError> 
+where F: FnOnce(Vec<FormattedData>) -> Result<String, OracleSqlToolsError> {
     let table_exists = does_table_exist(&grid_data.conn, &table_name)?;
-    let (data_header, data_body) = match table_exists {
+    let dialect = Dialect::resolve(grid_data.dialect, &grid_data.conn)?;
+    if !grid_data.options.extended_strings {
+        grid_data.options.extended_strings = has_extended_strings(&grid_data.conn);
+    }
+    let mut report = InsertReport {
+        table_created: !table_exists,
+        column_types: named_column_types(&grid_data, dialect, &grid_data.options),
+        ..Default::default()
+    };
+    let (data_header, mut data_body) = match table_exists {
         // if the user input table exists, it replaces the header with the column names from the table in order
         true => {
             let (data_header, _) = grid_data.data.replace_header(&grid_data.conn, &table_name)?;
@@ -58,7 +226,7 @@ fn stage_insert_data(mut grid_data: PreppedGridData, table_name: &str) -> Result
         },
         // if user input table does not exist, it creates a new table
         false => {
-            grid_data.data.create_table(table_name, &grid_data.data_indexes, &grid_data.conn)?;
+            report.create_table_stmt = Some(grid_data.data.create_table(table_name, &grid_data.data_indexes, dialect, &grid_data.options, &grid_data.conn)?);
             let (data_header, _) = grid_data.data.separate_header();
             (data_header, grid_data.data)
         },
@@ -70,10 +238,20 @@ fn stage_insert_data(mut grid_data: PreppedGridData, table_name: &str) -> Result
         }) 
     }
 
+    // the table is created by now, so the booleans can be swapped for what they're stored as
+    if !dialect.supports_boolean() {
+        grid_data.options.boolean_storage.store_grid(&mut data_body, &mut grid_data.data_indexes);
+    }
+
+    let insert_stmt = build_stmt(data_header)?;
+    report.insert_stmt = insert_stmt.to_owned();
     Ok(BatchPrep {
         data: data_body,
         conn: grid_data.conn,
-        insert_stmt: data_header.insert_stmt(table_name),
+        insert_stmt,
         data_indexes: grid_data.data_indexes,
+        options: grid_data.options,
+        progress: grid_data.progress,
+        report,
     })
 }
\ No newline at end of file
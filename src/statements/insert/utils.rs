@@ -1,86 +1,290 @@
-use std::{fmt::Display, sync::Arc, thread::{self, JoinHandle}};
-use indicatif::ProgressBar;
-use oracle::{Batch, Connection};
+use std::{fmt::Display, sync::{Arc, Mutex}, thread::{self, JoinHandle}, time::Instant};
+use oracle::{pool::Pool, sql_type::OracleType, Batch, Connection};
 
-use crate::{format_data::FormattedData, statements::{mutate_grid::MutateGrid, utils::remove_invalid_chars}, types::{errors::OracleSqlToolsError, BatchPrep, CellProperties, DatatypeIndexes, GridProperties}};
+use crate::{format_data::FormattedData, progress::ProgressObserver, utils::{to_hex, MAX_RAW_BIND_LENGTH}, statements::utils::remove_invalid_chars, types::{errors::OracleSqlToolsError, BatchPrep, CellProperties, CommitProgress, DatatypeIndexes, GridProperties, InsertOptions, InsertReport, RejectedRow, ThreadReport}};
 
 impl BatchPrep {
-    pub(crate) fn split_batch_by_threads(mut self) -> Result<Arc<Connection>, OracleSqlToolsError> {
+    pub(crate) fn split_batch_by_threads(mut self) -> Result<(Arc<Connection>, InsertReport), OracleSqlToolsError> {
+        let mut report = std::mem::take(&mut self.report);
+        let (conn, results) = self.run_on_shared_connection(None);
+        for res in results {
+            report.merge(res?);
+        }
+        Ok((conn, report))
+    }
+
+    pub(crate) fn split_batch_in_transaction(mut self) -> Result<(Arc<Connection>, InsertReport), OracleSqlToolsError> {
+        let mut report = std::mem::take(&mut self.report);
+        // the threads share one connection, so a commit from any of them commits the rows from all of them
+        let commits = self.options.commit_every.map(|commit_every| Arc::new(Mutex::new(CommitProgress {
+            commit_every,
+            pending: 0,
+            committed: 0,
+        })));
+        let (conn, results) = self.run_on_shared_connection(commits.clone());
+        let rows_committed = commits.as_ref().map_or(0, |commits| commits.lock().unwrap().committed);
+
+        let mut first_error: Option<OracleSqlToolsError> = None;
+        for res in results {
+            match res {
+                Ok(thread_report) => report.merge(thread_report),
+                Err(e) => { first_error.get_or_insert(e); },
+            }
+        }
+        match first_error {
+            Some(e) => {
+                // the thread's error is the one worth reporting, so a failed rollback doesn't replace it
+                let _ = conn.rollback();
+                Err(OracleSqlToolsError::TransactionRolledBack { rows_committed, source: Box::new(e) })
+            },
+            None => {
+                conn.commit()?;
+                report.rows_committed = report.rows_inserted;
+                Ok((conn, report))
+            },
+        }
+    }
+
+    /// Runs every slice of the data on its own thread, sharing the one connection, and waits for all of them to finish
+    fn run_on_shared_connection(self, commits: Option<Arc<Mutex<CommitProgress>>>) -> (Arc<Connection>, Vec<Result<InsertReport, OracleSqlToolsError>>) {
         // wrapping these variables in an Arc because they're going to be passed to multiple threads
         let conn: Arc<Connection> = Arc::new(self.conn);
         let insert_stmt: Arc<String> = Arc::new(self.insert_stmt);
         let datatype_indexes: Arc<DatatypeIndexes> = Arc::new(self.data_indexes);
 
-        // divides the length of the data by the number of threads on the host CPU
-        let len = self.data.len();
-        let nthreads = num_cpus::get();
-        let num = (len / nthreads + if len % nthreads == 0 { 0 } else { 1 }) as f32;
-
-        // initialize the progress bar
-        let pb = ProgressBar::new(len as u64);
-        let progress_bar = Arc::new(pb);
+        let slices = split_data(self.data, self.options.threads);
+        start_progress(self.progress.as_ref(), &slices);
 
         // captures the spawned threads into a vector
-        let mut handles: Vec<JoinHandle<Result<(), OracleSqlToolsError>>> = Vec::new();
-        // iterates as many times as there are threads
-        for n in 0..nthreads {
+        let mut handles: Vec<JoinHandle<Result<InsertReport, OracleSqlToolsError>>> = Vec::new();
+        for (thread, (num, arc_data)) in slices.into_iter().enumerate() {
             // each thread needs to have its own clone of the data
             let conn = Arc::clone(&conn);
             let insert = Arc::clone(&insert_stmt);
             let datatype_indexes = Arc::clone(&datatype_indexes);
-            let progress_bar = Arc::clone(&progress_bar);
-            let arc_data: Arc<Vec<Vec<FormattedData>>>;
-            if n + 1 < nthreads {
-                // splits up the 2d vector to have an even amount per thread
-                let d = self.data.divide(num);
-                // new ARC per each split vector
-                arc_data = Arc::new(d);
-            // collecting the remaining data
-            } else { arc_data = Arc::new(self.data.to_owned()); }
+            let progress = Arc::clone(&self.progress);
+            let options = self.options.clone();
+            let commits = commits.clone();
             handles.push(thread::spawn(move || {
-                // creates a unique Batch per thread
-                let mut batch: Batch<'_> = conn
-                    .batch(&insert.as_str(), arc_data.len())
-                    .build()?;
                 // each thread iterates over their slice of the data
                 GridProperties {
                     data: arc_data,
-                    num: (num.ceil() as usize - 1) * n,
+                    num,
                     datatype_indexes,
-                }.get_cell_props(&mut batch, progress_bar)
+                    thread,
+                    progress,
+                }.execute_on_session(&conn, &insert, &options, commits.as_deref())
             }));
         }
         // executes all threads
+        let results = handles.into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect();
+        self.progress.finish();
+        (conn, results)
+    }
+
+    pub(crate) fn split_batch_by_pool(mut self, pool: &Pool) -> Result<InsertReport, OracleSqlToolsError> {
+        let mut report = std::mem::take(&mut self.report);
+        let insert_stmt: Arc<String> = Arc::new(self.insert_stmt);
+        let datatype_indexes: Arc<DatatypeIndexes> = Arc::new(self.data_indexes);
+
+        // the connection that looked up or created the table goes back to the pool before the threads take their sessions
+        drop(self.conn);
+
+        // every session is acquired before any thread starts, so a pool that runs out of sessions doesn't leave rows half inserted
+        let slices = split_data(self.data, self.options.threads);
+        let mut sessions: Vec<Connection> = Vec::new();
+        for _ in 0..slices.len() { sessions.push(pool.get()?) }
+        start_progress(self.progress.as_ref(), &slices);
+
+        // each thread hands back its session so the transaction can be ended on all of them together
+        let mut handles = Vec::new();
+        for (thread, ((num, arc_data), conn)) in slices.into_iter().zip(sessions).enumerate() {
+            let insert = Arc::clone(&insert_stmt);
+            let datatype_indexes = Arc::clone(&datatype_indexes);
+            let progress = Arc::clone(&self.progress);
+            let options = self.options.clone();
+            handles.push(thread::spawn(move || {
+                // every session commits its own rows
+                let commits = options.commit_every.map(|commit_every| Mutex::new(CommitProgress {
+                    commit_every,
+                    pending: 0,
+                    committed: 0,
+                }));
+                let res = GridProperties {
+                    data: arc_data,
+                    num,
+                    datatype_indexes,
+                    thread,
+                    progress,
+                }.execute_on_session(&conn, &insert, &options, commits.as_ref());
+                let rows_committed = commits.map_or(0, |commits| commits.into_inner().unwrap().committed);
+                (conn, rows_committed, res)
+            }));
+        }
+
+        // each session with the rows it inserted and the rows it already committed itself
+        let mut sessions: Vec<(Connection, usize, usize)> = Vec::new();
+        let mut first_error: Option<OracleSqlToolsError> = None;
         for handle in handles {
-            handle.join().unwrap()?;
+            let (conn, committed, res) = handle.join().unwrap();
+            let mut inserted = 0usize;
+            match res {
+                Ok(thread_report) => {
+                    inserted = thread_report.rows_inserted;
+                    report.merge(thread_report);
+                },
+                Err(e) => { first_error.get_or_insert(e); },
+            }
+            sessions.push((conn, inserted, committed));
+        }
+        self.progress.finish();
+        // only commits if every thread succeeded, otherwise every session is rolled back
+        if let Some(e) = first_error {
+            rollback_sessions(&sessions);
+            let rows_committed = sessions.iter().map(|(_, _, committed)| committed).sum();
+            return Err(OracleSqlToolsError::TransactionRolledBack { rows_committed, source: Box::new(e) })
+        }
+        for (n, (conn, _, _)) in sessions.iter().enumerate() {
+            if let Err(e) = conn.commit() {
+                // the sessions before this one are already committed, so only the rest can be rolled back
+                rollback_sessions(&sessions[n..]);
+                let rows_committed = sessions[..n].iter().map(|(_, inserted, _)| inserted).sum::<usize>()
+                    + sessions[n..].iter().map(|(_, _, committed)| committed).sum::<usize>();
+                return Err(OracleSqlToolsError::TransactionRolledBack { rows_committed, source: Box::new(e.into()) })
+            }
         }
-        Ok(conn)
+        report.rows_committed = report.rows_inserted;
+        Ok(report)
     }
+}
 
-    pub(crate) fn single_thread_batch(self) -> Result<Arc<Connection>, OracleSqlToolsError> {
-        let body_len = &self.data.len();
-        // initialize the progress bar
-        let pb = ProgressBar::new(*body_len as u64);
-        let progress_bar = Arc::new(pb);
-        let conn: Arc<Connection> = Arc::new(self.conn);
-        let conn_clone = Arc::clone(&conn);
-        let mut batch: Batch<'_> = conn_clone
-            .batch(&self.insert_stmt.as_str(), body_len.to_owned())
-            .build()?;
-        // CellProperties expects an Arc<DatatypeIndexes>
-        let datatype_indexes = Arc::new(self.data_indexes);
-        GridProperties {
-            data: self.data.into(),
-            num: 0usize,
-            datatype_indexes,
-        }.get_cell_props(&mut batch, progress_bar)?;
-        Ok(conn)
+/// Rolls back every session, even if one of them fails
+///
+/// A session that can't be rolled back still has its transaction rolled back when it's released back to the pool.
+fn rollback_sessions(sessions: &[(Connection, usize, usize)]) {
+    for (conn, _, _) in sessions {
+        let _ = conn.rollback();
     }
 }
 
+/// Tells the observer how many rows each thread was given
+pub(crate) fn start_progress(progress: &dyn ProgressObserver, slices: &[(usize, Arc<Vec<Vec<FormattedData>>>)]) {
+    let rows_per_thread = slices.iter()
+        .map(|(_, slice)| slice.len())
+        .collect::<Vec<usize>>();
+    progress.start(rows_per_thread.iter().sum(), &rows_per_thread);
+}
+
+/// Divides the data as evenly as possible between the threads, returning each slice with the index of its first row
+pub(crate) fn split_data(data: Vec<Vec<FormattedData>>, nthreads: usize) -> Vec<(usize, Arc<Vec<Vec<FormattedData>>>)> {
+    let len = data.len();
+    // never starts more threads than there are rows
+    let nthreads = nthreads.clamp(1, len.max(1));
+    let rows_per_thread = len.div_ceil(nthreads);
+
+    let mut slices = Vec::new();
+    let mut rows = data.into_iter();
+    let mut num = 0usize;
+    while num < len {
+        let slice: Vec<Vec<FormattedData>> = rows.by_ref().take(rows_per_thread).collect();
+        let slice_len = slice.len();
+        slices.push((num, Arc::new(slice)));
+        num += slice_len;
+    }
+    slices
+}
+
+/// True if any value in the binary column is too long to bind as a `RAW`
+pub(crate) fn binds_as_long_raw(data: &[Vec<FormattedData>], x_index: usize) -> bool {
+    data.iter().any(|row| match row.get(x_index) {
+        Some(FormattedData::BINARY(val)) => val.len() > MAX_RAW_BIND_LENGTH,
+        _ => false,
+    })
+}
+
+/// How many rows each Batch execute takes from a slice, never more than the slice holds and never zero
+pub(crate) fn batch_rows(batch_size: usize, rows: usize) -> usize {
+    batch_size.clamp(1, rows.max(1))
+}
+
 impl GridProperties {
-    fn get_cell_props(self, batch: &mut Batch<'_>, progress_bar: Arc<ProgressBar>) -> Result<(), OracleSqlToolsError> {
-        self.data.iter().enumerate().try_for_each(|(y, row)| 
+    fn execute_on_session(self, conn: &Connection, insert_stmt: &str, options: &InsertOptions, commits: Option<&Mutex<CommitProgress>>) -> Result<InsertReport, OracleSqlToolsError> {
+        let started = Instant::now();
+        let (thread, rows) = (self.thread, self.data.len());
+        // the Batch never holds more than `batch_size` rows, so memory stays bounded no matter how large the slice is
+        let batch_size = batch_rows(options.batch_size, self.data.len());
+        let mut builder = conn.batch(insert_stmt, batch_size);
+        if options.batch_errors { builder.with_batch_errors(); }
+        let mut batch: Batch<'_> = builder.build()?;
+        self.set_bind_types(&mut batch)?;
+        let mut report = self.get_cell_props(conn, &mut batch, batch_size, options.batch_errors, commits)?;
+        report.threads.push(ThreadReport {
+            thread,
+            rows,
+            rows_inserted: report.rows_inserted,
+            duration: started.elapsed(),
+        });
+        Ok(report)
+    }
+
+    fn get_cell_props(self, conn: &Connection, batch: &mut Batch<'_>, batch_size: usize, batch_errors: bool, commits: Option<&Mutex<CommitProgress>>) -> Result<InsertReport, OracleSqlToolsError> {
+        let mut report = InsertReport::default();
+        for (chunk_index, chunk) in self.data.chunks(batch_size).enumerate() {
+            let chunk_start = self.num + chunk_index * batch_size;
+            // holding the lock while the chunk executes keeps another thread from committing it before it's counted
+            let mut commit_progress = commits.map(|commits| commits.lock().unwrap());
+            let rows_inserted = match self.bind_chunk(batch, chunk, chunk_start) {
+                Ok(_) => chunk.len(),
+                // the good rows in the chunk were still inserted, only the ones the database returned errors for are skipped
+                Err(OracleSqlToolsError::OracleError(oracle::Error::BatchErrors(errors))) if batch_errors => {
+                    let rows_inserted = chunk.len() - errors.len();
+                    for error in errors {
+                        let offset = error.offset() as usize;
+                        report.rejected_rows.push(RejectedRow {
+                            row_index: chunk_start + offset,
+                            values: chunk[offset].to_owned(),
+                            error,
+                        });
+                    }
+                    rows_inserted
+                },
+                Err(e) => return Err(e),
+            };
+            report.rows_inserted += rows_inserted;
+            if let Some(commit_progress) = commit_progress.as_mut() {
+                commit_progress.pending += rows_inserted;
+                if commit_progress.pending >= commit_progress.commit_every {
+                    conn.commit()?;
+                    commit_progress.committed += commit_progress.pending;
+                    commit_progress.pending = 0;
+                }
+            }
+            self.progress.rows_done(self.thread, chunk.len());
+        }
+        Ok(report)
+    }
+
+    /// Sets the bind types that can't be taken from the first value in each column
+    ///
+    /// Long strings are bound as `CLOB`s, bytes past the 32767 byte limit for a `RAW` bind as `LONG RAW`, 
+    /// and decimals as `NUMBER`s so their text isn't read with the session's decimal separator
+    pub(crate) fn set_bind_types(&self, batch: &mut Batch<'_>) -> Result<(), OracleSqlToolsError> {
+        for x in self.datatype_indexes.is_clob.iter() {
+            batch.set_type(x + 1, &OracleType::CLOB)?;
+        }
+        for x in self.datatype_indexes.is_decimal.iter() {
+            batch.set_type(x + 1, &OracleType::Number(0, 0))?;
+        }
+        for x in self.datatype_indexes.is_binary.iter() {
+            if binds_as_long_raw(&self.data, *x) { batch.set_type(x + 1, &OracleType::LongRaw)? }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn bind_chunk(&self, batch: &mut Batch<'_>, chunk: &[Vec<FormattedData>], chunk_start: usize) -> Result<(), OracleSqlToolsError> {
+        chunk.iter().enumerate().try_for_each(|(y, row)| 
         -> Result<(), OracleSqlToolsError> {
             row.iter().enumerate().try_for_each(|(x, cell)| 
             -> Result<(), OracleSqlToolsError> {
@@ -88,14 +292,15 @@ impl GridProperties {
                     cell,
                     datatype_indexes: &self.datatype_indexes,
                     x_ind: x,
-                    y_ind: self.num + y,
+                    y_ind: chunk_start + y,
                 }.bind_cell_to_batch(batch)
             })?;
+            // a chunk never holds more than `batch_size` rows, so the Batch can always take the whole chunk. 
+            // Without batch errors it executes itself once it's full, with them it waits for `.execute()`
             batch.append_row(&[])?;
-            progress_bar.inc(1u64);
             Ok(())
         })?;
-
+        // executes whatever the Batch hasn't executed itself yet
         batch.execute()?;
         Ok(())
     }
@@ -160,10 +365,43 @@ impl<'props> CellProperties<'props> {
                     _ => batch_set(self, batch, *val),
                 }
             },
+            // binds the offset along with the time, so the value isn't moved to the session's time zone
+            FormattedData::TIMESTAMPTZ(val) => match self.datatype_indexes.is_varchar.contains(&self.x_ind) {
+                true => batch_set(self, batch, val.to_string()),
+                false => batch_set(self, batch, *val),
+            },
+            FormattedData::BINARY(val) => match self.datatype_indexes.is_varchar.contains(&self.x_ind) {
+                true => batch_set(self, batch, to_hex(val)),
+                false => match batch.set(self.x_ind + 1, val) {
+                    Ok(_) => Ok(()),
+                    Err(e) => Err(OracleSqlToolsError::CellPropertyError { 
+                        error_message: e, 
+                        cell_value: to_hex(val),
+                        x_index: self.x_ind, 
+                        y_index: self.y_ind 
+                    }),
+                },
+            },
+            FormattedData::BOOLEAN(val) => match self.datatype_indexes {
+                ind if ind.is_varchar.contains(&self.x_ind) => batch_set(self, batch, val.to_string()),
+                ind if ind.is_int.contains(&self.x_ind) || ind.is_decimal.contains(&self.x_ind) => batch_set(self, batch, *val as i64),
+                ind if ind.is_float.contains(&self.x_ind) => batch_set(self, batch, *val as i64 as f64),
+                _ => batch_set(self, batch, *val),
+            },
+            #[cfg(feature = "decimal")]
+            FormattedData::DECIMAL(val) => match self.datatype_indexes.is_float.contains(&self.x_ind) {
+                true => batch_set(self, batch, val.to_string().parse::<f64>().unwrap()),
+                // the text is bound to a NUMBER in a decimal column and to a VARCHAR2 everywhere else
+                false => batch_set(self, batch, val.to_string()),
+            },
             FormattedData::EMPTY => {
                 match self.datatype_indexes {
                     ind if ind.is_varchar.contains(&self.x_ind) => empty_batch_set!(self, String, batch),
+                    ind if ind.is_binary.contains(&self.x_ind) => empty_batch_set!(self, Vec<u8>, batch),
+                    ind if ind.is_bool.contains(&self.x_ind) => empty_batch_set!(self, bool, batch),
+                    ind if ind.is_decimal.contains(&self.x_ind) => empty_batch_set!(self, i64, batch),
                     ind if ind.is_date.contains(&self.x_ind) => empty_batch_set!(self, chrono::NaiveDateTime, batch),
+                    ind if ind.is_timestamp_tz.contains(&self.x_ind) => empty_batch_set!(self, chrono::DateTime<chrono::FixedOffset>, batch),
                     ind if ind.is_int.contains(&self.x_ind) => empty_batch_set!(self, i8, batch),
                     ind if ind.is_float.contains(&self.x_ind) => empty_batch_set!(self, f32, batch),
                     _ => empty_batch_set!(self, String, batch),
@@ -185,4 +423,74 @@ where T: oracle::sql_type::ToSql + Display {
             y_index: cell_props.y_ind 
         }),
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use crate::{format_data::FormattedData, types::DatatypeIndexes, utils::MAX_RAW_BIND_LENGTH};
+    use super::{batch_rows, binds_as_long_raw, split_data};
+
+    fn grid(rows: usize) -> Vec<Vec<FormattedData>> {
+        (0..rows as i64).map(|row| vec![FormattedData::INT(row)]).collect()
+    }
+
+    fn slice_sizes(rows: usize, nthreads: usize) -> Vec<(usize, usize)> {
+        split_data(grid(rows), nthreads).into_iter().map(|(num, slice)| (num, slice.len())).collect()
+    }
+
+    #[test]
+    fn fewer_rows_than_threads() {
+        assert_eq!(slice_sizes(3, 8), vec![(0, 1), (1, 1), (2, 1)]);
+    }
+
+    #[test]
+    fn uneven_remainder_goes_to_the_last_slice() {
+        assert_eq!(slice_sizes(10, 4), vec![(0, 3), (3, 3), (6, 3), (9, 1)]);
+        let slices = split_data(grid(10), 4);
+        assert_eq!(slices[3].1[0], vec![FormattedData::INT(9)]);
+    }
+
+    #[test]
+    fn no_threads_or_no_rows() {
+        assert_eq!(slice_sizes(5, 0), vec![(0, 5)]);
+        assert!(split_data(grid(0), 4).is_empty());
+    }
+
+    #[test]
+    fn batch_size_larger_than_the_data() {
+        assert_eq!(batch_rows(10_000, 25), 25);
+        assert_eq!(grid(25).chunks(batch_rows(10_000, 25)).count(), 1);
+    }
+
+    #[test]
+    fn batch_size_splits_the_slice() {
+        assert_eq!(batch_rows(10, 25), 10);
+        let chunks = grid(25).chunks(batch_rows(10, 25)).map(|chunk| chunk.len()).collect::<Vec<usize>>();
+        assert_eq!(chunks, vec![10, 10, 5]);
+        assert_eq!(batch_rows(0, 25), 1);
+        assert_eq!(batch_rows(10, 0), 1);
+    }
+
+    #[test]
+    fn binary_columns_stay_binary_at_any_length() {
+        let grid = vec![
+            vec![FormattedData::STRING("short".to_string()), FormattedData::STRING("long".to_string())],
+            vec![FormattedData::BINARY(vec![0; 2000]), FormattedData::BINARY(vec![0; MAX_RAW_BIND_LENGTH + 1])],
+            vec![FormattedData::EMPTY, FormattedData::BINARY(vec![0; 1])],
+        ];
+        assert_eq!(DatatypeIndexes::from_grid(&grid).is_binary, vec![0, 1]);
+    }
+
+    #[test]
+    fn long_raw_only_past_the_raw_bind_limit() {
+        let grid = |len: usize| vec![
+            vec![FormattedData::STRING("data".to_string())],
+            vec![FormattedData::BINARY(vec![0; 10])],
+            vec![FormattedData::BINARY(vec![0; len])],
+        ];
+        assert!(!binds_as_long_raw(&grid(2000), 0));
+        assert!(!binds_as_long_raw(&grid(2001), 0));
+        assert!(!binds_as_long_raw(&grid(MAX_RAW_BIND_LENGTH), 0));
+        assert!(binds_as_long_raw(&grid(MAX_RAW_BIND_LENGTH + 1), 0));
+        assert!(!binds_as_long_raw(&grid(MAX_RAW_BIND_LENGTH + 1), 1));
+    }
+}
@@ -1,6 +1,7 @@
+use std::sync::Arc;
 use oracle::Connection;
 
-use crate::{format_data::FormattedData, types::DatatypeIndexes};
+use crate::{format_data::FormattedData, progress::ProgressObserver, types::{BooleanStorage, DatatypeIndexes, Dialect, Filter, InsertOptions}};
 
 pub mod mutate_grid;
 pub mod mutate_row;
@@ -8,12 +9,20 @@ pub mod create_table;
 pub mod insert;
 pub mod utils;
 pub mod select;
+pub mod upsert;
+pub mod update;
+pub mod delete;
+pub mod reload;
+pub mod script;
 
 #[derive(Debug)]
 pub struct PreppedGridData {
     pub data: Vec<Vec<FormattedData>>,
     pub conn: Connection,
     pub data_indexes: DatatypeIndexes,
+    pub options: InsertOptions,
+    pub progress: Arc<dyn ProgressObserver>,
+    pub dialect: Option<Dialect>,
 }
 
 #[derive(Debug)]
@@ -22,5 +31,18 @@ pub struct PreppedRowData {
     pub conn: Connection,
     pub query: Option<String>,
     pub header: Option<Vec<String>>,
-    pub filters: Option<Vec<String>>,
-}
\ No newline at end of file
+    pub filters: Option<Vec<Filter>>,
+    pub limit: Option<usize>,
+    pub dialect: Option<Dialect>,
+    pub boolean_storage: BooleanStorage,
+}
+
+#[derive(Debug)]
+pub struct PreppedDelete<'a> {
+    pub conn: &'a Connection,
+    pub table_name: String,
+    pub filters: Vec<Filter>,
+    pub delete_all: bool,
+    pub dialect: Option<Dialect>,
+    pub boolean_storage: BooleanStorage,
+}
@@ -4,7 +4,6 @@ use crate::{format_data::FormattedData, types::errors::OracleSqlToolsError};
 pub(crate) trait MutateGrid {
     fn replace_header(&mut self, connection: &Connection, table_name: &str) -> Result<(Vec<FormattedData>, &Self), OracleSqlToolsError>;
     fn separate_header(&mut self) -> (Vec<FormattedData>, &Self);
-    fn divide(&mut self, num: f32) -> Self;
 }
 
 impl MutateGrid for Vec<Vec<FormattedData>> {
@@ -45,12 +44,4 @@ impl MutateGrid for Vec<Vec<FormattedData>> {
         let res = &header[0];
         (res.to_owned(), self)
     }
-
-    fn divide(&mut self, num: f32) -> Self {
-        let res: Vec<_> = self.splice(
-            (0)..(num.ceil() as usize - 1),
-            []
-        ).collect();
-        res
-    }
 }
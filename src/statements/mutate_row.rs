@@ -1,8 +1,10 @@
-use crate::format_data::FormattedData;
+use crate::{format_data::FormattedData, types::errors::OracleSqlToolsError, utils::to_hex};
 use super::utils::remove_invalid_chars;
 
 pub(crate) trait MutateRow {
     fn insert_stmt(self, table_name: &str) -> String;
+    fn merge_stmt(self, table_name: &str, key_columns: &[&str]) -> Result<String, OracleSqlToolsError>;
+    fn update_stmt(self, table_name: &str, key_columns: &[&str]) -> Result<(String, Vec<usize>), OracleSqlToolsError>;
     fn to_string(&self) -> Vec<String>;
 }
 
@@ -15,6 +17,11 @@ macro_rules! to_string {
                 FormattedData::FLOAT(val) => val.to_string(),
                 FormattedData::DATE(val) => val.to_string(),
                 FormattedData::TIMESTAMP(val) => val.to_string(),
+                FormattedData::TIMESTAMPTZ(val) => val.to_string(),
+                FormattedData::BINARY(val) => to_hex(val),
+                FormattedData::BOOLEAN(val) => val.to_string(),
+                #[cfg(feature = "decimal")]
+                FormattedData::DECIMAL(val) => val.to_string(),
                 FormattedData::EMPTY => "".to_string(),
             }
         }).collect::<Vec<String>>()
@@ -37,5 +44,126 @@ impl MutateRow for Vec<FormattedData> {
         insert
     }
     
+    fn merge_stmt(self, table_name: &str, key_columns: &[&str]) -> Result<String, OracleSqlToolsError> {
+        let header = to_string!(self).iter().map(remove_invalid_chars).collect::<Vec<String>>();
+        let keys = find_key_columns(&header, key_columns)?;
+
+        // each row is selected from dual with its values bound in the same order as the header
+        let source = header.iter().enumerate()
+            .map(|(i, col)| format!(":{} AS {}", i + 1, col))
+            .collect::<Vec<String>>();
+        let on = keys.iter()
+            .map(|key| format!("t.{} = s.{}", key, key))
+            .collect::<Vec<String>>();
+        let updates = header.iter()
+            .filter(|col| !keys.contains(col))
+            .map(|col| format!("t.{} = s.{}", col, col))
+            .collect::<Vec<String>>();
+        let values = header.iter()
+            .map(|col| format!("s.{}", col))
+            .collect::<Vec<String>>();
+
+        let mut merge = format!(
+            "MERGE INTO {} t USING (SELECT {} FROM dual) s ON ({})", 
+            table_name, source.join(", "), on.join(" AND ")
+        );
+        // if every column is a key there's nothing to update
+        if !updates.is_empty() { 
+            merge = format!("{} WHEN MATCHED THEN UPDATE SET {}", merge, updates.join(", ")) 
+        }
+        Ok(format!(
+            "{} WHEN NOT MATCHED THEN INSERT ({}) VALUES ({})", 
+            merge, header.join(", "), values.join(", ")
+        ))
+    }
+    
+    fn update_stmt(self, table_name: &str, key_columns: &[&str]) -> Result<(String, Vec<usize>), OracleSqlToolsError> {
+        let header = to_string!(self).iter().map(remove_invalid_chars).collect::<Vec<String>>();
+        let keys = find_key_columns(&header, key_columns)?;
+
+        // placeholders are bound in the order they appear, so the columns being set come first and the keys last
+        let (key_indexes, set_indexes): (Vec<usize>, Vec<usize>) = (0..header.len())
+            .partition(|i| keys.contains(&header[*i]));
+        if set_indexes.is_empty() { return Err(OracleSqlToolsError::NoColumnsToUpdate) }
+
+        let set = set_indexes.iter().enumerate()
+            .map(|(n, i)| format!("{} = :{}", header[*i], n + 1))
+            .collect::<Vec<String>>();
+        let conditions = key_indexes.iter().enumerate()
+            .map(|(n, i)| format!("{} = :{}", header[*i], set_indexes.len() + n + 1))
+            .collect::<Vec<String>>();
+
+        let update = format!(
+            "UPDATE {} SET {} WHERE {}", 
+            table_name, set.join(", "), conditions.join(" AND ")
+        );
+        Ok((update, set_indexes.into_iter().chain(key_indexes).collect()))
+    }
+
     fn to_string(&self) -> Vec<String> { to_string!(self) }
-}
\ No newline at end of file
+}
+
+/// Matches the key columns to the column names in the header, ignoring case
+pub(crate) fn find_key_columns(header: &[String], key_columns: &[&str]) -> Result<Vec<String>, OracleSqlToolsError> {
+    if key_columns.is_empty() { return Err(OracleSqlToolsError::NoKeyColumns) }
+    key_columns.iter().map(|key| {
+        let key = remove_invalid_chars(&key.to_string());
+        match header.iter().find(|col| col.eq_ignore_ascii_case(&key)) {
+            Some(col) => Ok(col.to_owned()),
+            None => Err(OracleSqlToolsError::InvalidKeyColumn { column: key }),
+        }
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{format_data::FormattedData, types::errors::OracleSqlToolsError};
+    use super::MutateRow;
+
+    fn header(columns: &[&str]) -> Vec<FormattedData> {
+        columns.iter().map(|col| FormattedData::STRING(col.to_string())).collect()
+    }
+
+    #[test]
+    fn merge_binds_the_header_in_order() {
+        let merge = header(&["ID", "Name", "Hire Date"]).merge_stmt("MY_TABLE", &["id"]).unwrap();
+        assert_eq!(merge, "MERGE INTO MY_TABLE t USING (SELECT :1 AS ID, :2 AS Name, :3 AS Hire_Date FROM dual) s ON (t.ID = s.ID) \
+            WHEN MATCHED THEN UPDATE SET t.Name = s.Name, t.Hire_Date = s.Hire_Date \
+            WHEN NOT MATCHED THEN INSERT (ID, Name, Hire_Date) VALUES (s.ID, s.Name, s.Hire_Date)");
+    }
+
+    #[test]
+    fn merge_joins_on_every_key() {
+        let merge = header(&["A", "B", "C"]).merge_stmt("MY_TABLE", &["A", "C"]).unwrap();
+        assert!(merge.contains("ON (t.A = s.A AND t.C = s.C) WHEN MATCHED THEN UPDATE SET t.B = s.B WHEN"), "{}", merge);
+    }
+
+    #[test]
+    fn merge_skips_the_update_when_every_column_is_a_key() {
+        let merge = header(&["A", "B"]).merge_stmt("MY_TABLE", &["A", "B"]).unwrap();
+        assert_eq!(merge, "MERGE INTO MY_TABLE t USING (SELECT :1 AS A, :2 AS B FROM dual) s ON (t.A = s.A AND t.B = s.B) \
+            WHEN NOT MATCHED THEN INSERT (A, B) VALUES (s.A, s.B)");
+    }
+
+    #[test]
+    fn merge_checks_the_key_columns() {
+        assert!(matches!(header(&["A"]).merge_stmt("MY_TABLE", &[]), Err(OracleSqlToolsError::NoKeyColumns)));
+        assert!(matches!(
+            header(&["A", "B"]).merge_stmt("MY_TABLE", &["C"]), 
+            Err(OracleSqlToolsError::InvalidKeyColumn { column }) if column == "C"
+        ));
+    }
+
+    #[test]
+    fn update_binds_the_keys_last() {
+        let (update, order) = header(&["A", "ID", "B", "Region"]).update_stmt("MY_TABLE", &["Region", "ID"]).unwrap();
+        assert_eq!(update, "UPDATE MY_TABLE SET A = :1, B = :2 WHERE ID = :3 AND Region = :4");
+        assert_eq!(order, vec![0, 2, 1, 3]);
+    }
+
+    #[test]
+    fn update_checks_the_key_columns() {
+        assert!(matches!(header(&["A", "B"]).update_stmt("MY_TABLE", &[]), Err(OracleSqlToolsError::NoKeyColumns)));
+        assert!(matches!(header(&["A", "B"]).update_stmt("MY_TABLE", &["A", "B"]), Err(OracleSqlToolsError::NoColumnsToUpdate)));
+    }
+}
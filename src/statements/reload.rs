@@ -0,0 +1,147 @@
+use std::sync::Arc;
+use oracle::Connection;
+
+use crate::{format_data::FormattedData, types::{errors::OracleSqlToolsError, ClauseType, Condition, Operator}, DeleteFrom};
+use super::{insert::{stage_insert_data, without_rejected_rows}, mutate_row::{find_key_columns, MutateRow}, utils::remove_invalid_chars, PreppedGridData};
+
+// Oracle allows at most 1000 values in an IN list, so the slices are deleted in groups of this size
+const SLICES_PER_DELETE: usize = 1000;
+
+impl PreppedGridData {
+    /// Replaces the slice of the table covered by the input data
+    ///
+    /// Finds the distinct values of the key columns in the input data, deletes the rows in the table with those values, then inserts the input data the same way as [`.insert()`](`crate::statements::insert::PreppedGridData::insert`). 
+    /// Returns the connection along with the number of rows that were deleted.
+    /// 
+    /// Nothing is committed, so the delete and the insert are in the same transaction. If the insert fails, the connection is dropped and both are rolled back together.
+    /// 
+    /// # Usage
+    /// 
+    /// ```no_run
+    /// let conn: oracle::Connection = match Connection::connect("<USERNAME>", "<PASSWORD>", "<IP ADDRESS>")?; 
+    /// 
+    /// let data: Vec<Vec<String>> = vec![
+    ///     vec!["Period".to_string(), "Business Unit".to_string(), "Amount".to_string()],
+    ///     vec!["2024-06".to_string(), "Retail".to_string(), "100".to_string()],
+    ///     vec!["2024-06".to_string(), "Retail".to_string(), "250".to_string()],
+    ///     vec!["2024-06".to_string(), "Wholesale".to_string(), "75".to_string()],
+    /// ];
+    /// 
+    /// let (res, rows_deleted): (Arc<Connection>, u64) = data.prep_data(conn).reload("MY_TABLE", &["Period", "Business Unit"])?;
+    /// res.commit()?;
+    /// Ok(())
+    /// ```
+    /// Is the same as running:
+    /// ```sql
+    /// DELETE FROM my_table WHERE ((Period = :1 AND Business_Unit = :2) OR (Period = :3 AND Business_Unit = :4));
+    /// INSERT INTO my_table (Period, Business_Unit, Amount) VALUES (:1, :2, :3);
+    /// ```
+    pub fn reload(self, table_name: &str, key_columns: &[&str]) -> Result<(Arc<Connection>, u64), OracleSqlToolsError> {
+        if self.data.len() <= 1 { return Err(OracleSqlToolsError::NoData) }
+
+        let header = MutateRow::to_string(&self.data[0]).iter()
+            .map(remove_invalid_chars)
+            .collect::<Vec<String>>();
+        let keys = find_key_columns(&header, key_columns)?;
+        let key_indexes = keys.iter()
+            .filter_map(|key| header.iter().position(|col| col == key))
+            .collect::<Vec<usize>>();
+
+        // the distinct combinations of key values in the input data
+        let mut slices: Vec<Vec<FormattedData>> = Vec::new();
+        for row in self.data.iter().skip(1) {
+            let slice = key_indexes.iter()
+                .map(|i| row.get(*i).cloned().unwrap_or(FormattedData::EMPTY))
+                .collect::<Vec<FormattedData>>();
+            if !slices.contains(&slice) { slices.push(slice) }
+        }
+
+        // the key values still hold the grid's booleans, so the delete is told how they're stored
+        let (dialect, boolean_storage) = (self.dialect, self.options.boolean_storage);
+        let batch_prep = stage_insert_data(self, table_name, |header| Ok(header.insert_stmt(table_name)))?;
+        let mut rows_deleted = 0u64;
+        for group in slices.chunks(SLICES_PER_DELETE) {
+            let mut delete = batch_prep.conn.delete_from(table_name).with_boolean_storage(boolean_storage);
+            if let Some(dialect) = dialect { delete = delete.with_dialect(dialect) }
+            let deleted = delete
+                .filter_condition(ClauseType::Where, slice_condition(&keys, group))
+                .execute();
+            match deleted {
+                Ok(count) => rows_deleted += count,
+                Err(e) => {
+                    batch_prep.conn.rollback()?;
+                    return Err(e)
+                },
+            }
+        }
+
+        let conn = without_rejected_rows(batch_prep.split_batch_by_threads()?)?;
+        Ok((conn, rows_deleted))
+    }
+}
+
+/// Matches the rows in the table that have any of the combinations of key values
+fn slice_condition(keys: &[String], slices: &[Vec<FormattedData>]) -> Condition {
+    match keys {
+        // a single key column can use IN, but NULL has to be checked separately since it's never equal to anything
+        [key] => {
+            let (nulls, values): (Vec<FormattedData>, Vec<FormattedData>) = slices.iter()
+                .map(|slice| slice[0].to_owned())
+                .partition(|value| *value == FormattedData::EMPTY);
+            match (values.is_empty(), nulls.is_empty()) {
+                (false, true) => Condition::new(key, Operator::In(values)),
+                (false, false) => Condition::new(key, Operator::In(values)).or(Condition::new(key, Operator::IsNull)),
+                // an empty IN list would make the delete look unfiltered
+                (true, _) => Condition::new(key, Operator::IsNull),
+            }
+        },
+        _ => Condition::Or(slices.iter().map(|slice| {
+            Condition::And(keys.iter().zip(slice).map(|(key, value)| match value {
+                FormattedData::EMPTY => Condition::new(key, Operator::IsNull),
+                value => Condition::new(key, Operator::Equal(value.to_owned())),
+            }).collect())
+        }).collect()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{format_data::FormattedData, types::Condition};
+    use super::slice_condition;
+
+    fn keys(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    fn sql(condition: &Condition) -> (String, usize) {
+        let mut binds = Vec::new();
+        (condition.to_sql(&mut binds), binds.len())
+    }
+
+    #[test]
+    fn single_key_uses_in() {
+        let condition = slice_condition(&keys(&["id"]), &[vec![FormattedData::INT(1)], vec![FormattedData::INT(2)]]);
+        assert_eq!(sql(&condition), ("id IN (:1, :2)".to_string(), 2));
+        assert!(condition.filters_column());
+    }
+
+    #[test]
+    fn single_key_with_nulls_adds_is_null() {
+        let condition = slice_condition(&keys(&["id"]), &[vec![FormattedData::INT(1)], vec![FormattedData::EMPTY]]);
+        assert_eq!(sql(&condition), ("(id IN (:1) OR id IS NULL)".to_string(), 1));
+    }
+
+    #[test]
+    fn single_key_null_in_every_row() {
+        let condition = slice_condition(&keys(&["id"]), &[vec![FormattedData::EMPTY], vec![FormattedData::EMPTY]]);
+        assert_eq!(sql(&condition), ("id IS NULL".to_string(), 0));
+        assert!(condition.filters_column());
+    }
+
+    #[test]
+    fn composite_keys_match_each_combination() {
+        let slices = [vec![FormattedData::INT(1), FormattedData::EMPTY], vec![FormattedData::INT(2), FormattedData::INT(3)]];
+        let condition = slice_condition(&keys(&["id", "region"]), &slices);
+        assert_eq!(sql(&condition), ("((id = :1 AND region IS NULL) OR (id = :2 AND region = :3))".to_string(), 3));
+    }
+}
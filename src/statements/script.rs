@@ -0,0 +1,282 @@
+use std::{fs, path::Path};
+
+use crate::{format_data::FormattedData, types::{errors::OracleSqlToolsError, DatatypeIndexes, Dialect, InsertOptions}, utils::to_hex};
+use super::{create_table::CreateFromInsert, mutate_row::MutateRow, utils::remove_invalid_chars, PreppedGridData};
+
+// keeps each statement small enough for the database to parse quickly
+const ROWS_PER_STATEMENT: usize = 500;
+// a string literal can't be longer than 4000 bytes in SQL
+const MAX_LITERAL_LENGTH: usize = 4000;
+
+/// A trait to export the data as a SQL script instead of inserting it
+///
+/// The script creates the table, inserts every row as literal values and commits. It can be run in SQL*Plus, SQLcl or SQL Developer. 
+/// The rows are written as `INSERT ALL` blocks, or multi-row `VALUES` if the [`Dialect`] is 23ai or later.
+/// The script isn't tied to a database, so `VARCHAR2` and `RAW` columns are only sized past 4000 and 2000 bytes when [`InsertOptions::extended_strings`] is set.
+///
+/// ```no_run
+/// let data: Vec<Vec<FormattedData>> = vec![
+///     vec!["ColA".fmt_data(), "ColB".fmt_data(), "ColC".fmt_data()],
+///     vec!["O'Brien".fmt_data(), 1.fmt_data(), FormattedData::DATE(NaiveDate::from_ymd_opt(2024, 1, 31).unwrap())],
+/// ];
+///
+/// data.write_sql_script("MY_TABLE", Dialect::Oracle19c, "my_table.sql")?;
+/// ```
+/// Writes:
+/// ```sql
+/// SET DEFINE OFF;
+///
+/// CREATE TABLE MY_TABLE (ColA VARCHAR2(7), ColB NUMBER, ColC DATE);
+///
+/// INSERT ALL
+///     INTO MY_TABLE (ColA, ColB, ColC) VALUES ('O''Brien', 1, TO_DATE('2024-01-31', 'YYYY-MM-DD'))
+/// SELECT 1 FROM dual;
+///
+/// COMMIT;
+/// ```
+pub trait SqlScript {
+    /// Returns the script as a string
+    fn sql_script(&self, table_name: &str, dialect: Dialect) -> Result<String, OracleSqlToolsError>;
+
+    /// Writes the script to a file
+    fn write_sql_script<P: AsRef<Path>>(&self, table_name: &str, dialect: Dialect, path: P) -> Result<(), OracleSqlToolsError> {
+        fs::write(path, self.sql_script(table_name, dialect)?)?;
+        Ok(())
+    }
+}
+
+impl SqlScript for PreppedGridData {
+    fn sql_script(&self, table_name: &str, dialect: Dialect) -> Result<String, OracleSqlToolsError> {
+        build_script(&self.data, &self.data_indexes, table_name, dialect, &self.options)
+    }
+}
+
+impl SqlScript for Vec<Vec<FormattedData>> {
+    fn sql_script(&self, table_name: &str, dialect: Dialect) -> Result<String, OracleSqlToolsError> {
+        build_script(self, &DatatypeIndexes::from_grid(self), table_name, dialect, &InsertOptions::default())
+    }
+}
+
+fn build_script(data: &Vec<Vec<FormattedData>>, data_indexes: &DatatypeIndexes, table_name: &str, dialect: Dialect, options: &InsertOptions) -> Result<String, OracleSqlToolsError> {
+    let create_table_stmt = data.create_table_stmt(table_name, data_indexes, dialect, options)?;
+    let columns = data[0].to_string().iter()
+        .map(remove_invalid_chars)
+        .collect::<Vec<String>>();
+    if columns.len() != data[1].len() { 
+        return Err(OracleSqlToolsError::InvalidHeaderLength { 
+            header_length: columns.len(), 
+            body_length: data[1].len(), 
+        }) 
+    }
+    let columns = columns.join(", ");
+
+    let rows = data.iter().skip(1).map(|row| {
+        let values = row.iter().enumerate()
+            .map(|(x, cell)| match cell {
+                FormattedData::BOOLEAN(val) if data_indexes.is_bool.contains(&x) => match dialect.supports_boolean() {
+                    true => val.to_string().to_uppercase(),
+                    false => sql_literal(&options.boolean_storage.store(*val), false),
+                },
+                cell => sql_literal(cell, data_indexes.is_varchar.contains(&x)),
+            })
+            .collect::<Vec<String>>();
+        format!("({})", values.join(", "))
+    }).collect::<Vec<String>>();
+
+    // `&` would be read as a substitution variable by SQL*Plus
+    let mut script = vec!["SET DEFINE OFF;".to_string(), format!("{};", create_table_stmt)];
+    for chunk in rows.chunks(ROWS_PER_STATEMENT) {
+        let insert = match dialect.supports_multi_row_values() {
+            false => {
+                let into = chunk.iter()
+                    .map(|values| format!("    INTO {} ({}) VALUES {}", table_name, columns, values))
+                    .collect::<Vec<String>>();
+                format!("INSERT ALL\n{}\nSELECT 1 FROM dual;", into.join("\n"))
+            },
+            true => {
+                let values = chunk.iter()
+                    .map(|values| format!("    {}", values))
+                    .collect::<Vec<String>>();
+                format!("INSERT INTO {} ({}) VALUES\n{};", table_name, columns, values.join(",\n"))
+            },
+        };
+        script.push(insert);
+    }
+    script.push("COMMIT;".to_string());
+    Ok(script.join("\n\n") + "\n")
+}
+
+/// Writes a cell as a SQL literal, every value in a `VARCHAR2` column is written as a string
+fn sql_literal(cell: &FormattedData, is_varchar: bool) -> String {
+    match cell {
+        FormattedData::EMPTY => "NULL".to_string(),
+        cell if is_varchar => quote(&cell.to_owned().to_string()),
+        FormattedData::STRING(val) => quote(val),
+        FormattedData::INT(val) => val.to_string(),
+        // the FLOAT columns created from the data can't store NaN or infinity
+        FormattedData::FLOAT(val) if !val.is_finite() => "NULL".to_string(),
+        FormattedData::FLOAT(val) => val.to_string(),
+        FormattedData::DATE(val) => format!("TO_DATE('{}', 'YYYY-MM-DD')", val.format("%Y-%m-%d")),
+        FormattedData::TIMESTAMP(val) => format!(
+            "TO_TIMESTAMP('{}', 'YYYY-MM-DD HH24:MI:SS.FF6')", 
+            val.format("%Y-%m-%d %H:%M:%S%.6f")
+        ),
+        FormattedData::TIMESTAMPTZ(val) => format!(
+            "TO_TIMESTAMP_TZ('{}', 'YYYY-MM-DD HH24:MI:SS.FF6 TZH:TZM')", 
+            val.format("%Y-%m-%d %H:%M:%S%.6f %:z")
+        ),
+        FormattedData::BINARY(val) if val.is_empty() => "NULL".to_string(),
+        FormattedData::BINARY(val) => format!("HEXTORAW('{}')", to_hex(val)),
+        // booleans mixed into a number column are written as 1 or 0
+        FormattedData::BOOLEAN(val) => (*val as i64).to_string(),
+        #[cfg(feature = "decimal")]
+        FormattedData::DECIMAL(val) => val.to_string(),
+    }
+}
+
+/// Wraps a string in single quotes, doubling any quotes inside it
+///
+/// Strings too long for one literal are split into pieces that are joined back together as a `CLOB`
+fn quote(val: &str) -> String {
+    let escaped = val.replace('\'', "''");
+    if escaped.len() <= MAX_LITERAL_LENGTH { return format!("'{}'", escaped) }
+    // the pieces are measured once their quotes are doubled, and never split a character or a doubled quote
+    let mut pieces = Vec::new();
+    let mut piece = String::new();
+    for c in val.chars() {
+        let len = if c == '\'' { 2 } else { c.len_utf8() };
+        if piece.len() + len > MAX_LITERAL_LENGTH {
+            pieces.push(format!("TO_CLOB('{}')", piece));
+            piece.clear();
+        }
+        match c {
+            '\'' => piece.push_str("''"),
+            c => piece.push(c),
+        }
+    }
+    pieces.push(format!("TO_CLOB('{}')", piece));
+    pieces.join(" || ")
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, NaiveDate};
+
+    use crate::format_data::FormattedData;
+    use super::{quote, sql_literal, SqlScript, MAX_LITERAL_LENGTH};
+
+    /// The text each `TO_CLOB('...')` piece holds, with its quotes still doubled
+    fn clob_pieces(literal: &str) -> Vec<&str> {
+        literal.split(" || ")
+            .map(|piece| piece.strip_prefix("TO_CLOB('").unwrap().strip_suffix("')").unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn quote_doubles_single_quotes() {
+        assert_eq!(quote("O'Brien"), "'O''Brien'");
+        assert_eq!(quote("''"), "''''''");
+        assert_eq!(quote(""), "''");
+    }
+
+    #[test]
+    fn quote_splits_long_multibyte_strings_at_char_boundaries() {
+        // 3 bytes each, so 4000 bytes falls in the middle of a character
+        let val = "€".repeat(2000);
+        let literal = quote(&val);
+        let pieces = clob_pieces(&literal);
+        assert!(pieces.len() > 1);
+        assert!(pieces.iter().all(|piece| piece.len() <= MAX_LITERAL_LENGTH));
+        assert_eq!(pieces.concat(), val);
+    }
+
+    #[test]
+    fn quote_keeps_doubled_quotes_in_one_piece() {
+        let val = format!("{}'{}", "a".repeat(MAX_LITERAL_LENGTH - 1), "b".repeat(10));
+        let literal = quote(&val);
+        let pieces = clob_pieces(&literal);
+        assert!(pieces.iter().all(|piece| piece.len() <= MAX_LITERAL_LENGTH));
+        assert_eq!(pieces, vec!["a".repeat(MAX_LITERAL_LENGTH - 1), format!("''{}", "b".repeat(10))]);
+    }
+
+    #[test]
+    fn quote_measures_strings_with_their_quotes_doubled() {
+        let val = "'".repeat(MAX_LITERAL_LENGTH / 2 + 1);
+        let literal = quote(&val);
+        assert!(literal.starts_with("TO_CLOB('"));
+        assert_eq!(clob_pieces(&literal).concat(), val.replace('\'', "''"));
+    }
+
+    #[test]
+    fn sql_literal_writes_null_for_nan_and_empty_bytes() {
+        assert_eq!(sql_literal(&FormattedData::FLOAT(f64::NAN), false), "NULL");
+        assert_eq!(sql_literal(&FormattedData::FLOAT(f64::INFINITY), false), "NULL");
+        assert_eq!(sql_literal(&FormattedData::BINARY(Vec::new()), false), "NULL");
+        assert_eq!(sql_literal(&FormattedData::EMPTY, true), "NULL");
+    }
+
+    #[test]
+    fn sql_literal_formats_dates() {
+        let date = NaiveDate::from_ymd_opt(2024, 1, 31).unwrap();
+        assert_eq!(sql_literal(&FormattedData::DATE(date), false), "TO_DATE('2024-01-31', 'YYYY-MM-DD')");
+
+        let timestamp = date.and_hms_micro_opt(13, 5, 9, 120).unwrap();
+        assert_eq!(
+            sql_literal(&FormattedData::TIMESTAMP(timestamp), false), 
+            "TO_TIMESTAMP('2024-01-31 13:05:09.000120', 'YYYY-MM-DD HH24:MI:SS.FF6')"
+        );
+
+        let timestamptz = DateTime::parse_from_rfc3339("2024-01-31T13:05:09.5-05:30").unwrap();
+        assert_eq!(
+            sql_literal(&FormattedData::TIMESTAMPTZ(timestamptz), false), 
+            "TO_TIMESTAMP_TZ('2024-01-31 13:05:09.500000 -05:30', 'YYYY-MM-DD HH24:MI:SS.FF6 TZH:TZM')"
+        );
+    }
+
+    #[test]
+    fn sql_literal_quotes_everything_in_a_varchar_column() {
+        assert_eq!(sql_literal(&FormattedData::INT(5), true), "'5'");
+        assert_eq!(sql_literal(&FormattedData::INT(5), false), "5");
+    }
+
+    #[test]
+    fn script_renders_each_literal_kind() {
+        let date = NaiveDate::from_ymd_opt(2024, 1, 31).unwrap();
+        let data: Vec<Vec<FormattedData>> = vec![
+            ["Name", "Count", "Ratio", "Day", "Stamp", "StampTz", "File", "Flag", "Note"].iter()
+                .map(|col| FormattedData::STRING(col.to_string()))
+                .collect(),
+            vec![
+                FormattedData::STRING("O'Brien".to_string()),
+                FormattedData::INT(7),
+                FormattedData::FLOAT(1.5),
+                FormattedData::DATE(date),
+                FormattedData::TIMESTAMP(date.and_hms_opt(8, 30, 0).unwrap()),
+                FormattedData::TIMESTAMPTZ(DateTime::parse_from_rfc3339("2024-01-31T08:30:00+02:00").unwrap()),
+                FormattedData::BINARY(vec![0xDE, 0xAD]),
+                FormattedData::BOOLEAN(true),
+                FormattedData::EMPTY,
+            ],
+        ];
+        let script = data.sql_script("MY_TABLE", crate::types::Dialect::Oracle19c).unwrap();
+        let values = "VALUES ('O''Brien', 7, 1.5, TO_DATE('2024-01-31', 'YYYY-MM-DD'), \
+            TO_TIMESTAMP('2024-01-31 08:30:00.000000', 'YYYY-MM-DD HH24:MI:SS.FF6'), \
+            TO_TIMESTAMP_TZ('2024-01-31 08:30:00.000000 +02:00', 'YYYY-MM-DD HH24:MI:SS.FF6 TZH:TZM'), \
+            HEXTORAW('DEAD'), 1, NULL)";
+        assert!(script.starts_with("SET DEFINE OFF;\n\nCREATE TABLE MY_TABLE ("));
+        assert!(script.contains("INSERT ALL\n    INTO MY_TABLE (Name, Count, Ratio, Day, Stamp, StampTz, File, Flag, Note) "));
+        assert!(script.contains(values), "{}", script);
+        assert!(script.ends_with("SELECT 1 FROM dual;\n\nCOMMIT;\n"));
+    }
+
+    #[test]
+    fn script_uses_multi_row_values_on_23ai() {
+        let data: Vec<Vec<FormattedData>> = vec![
+            vec![FormattedData::STRING("Flag".to_string())],
+            vec![FormattedData::BOOLEAN(true)],
+            vec![FormattedData::BOOLEAN(false)],
+        ];
+        let script = data.sql_script("MY_TABLE", crate::types::Dialect::Oracle23ai).unwrap();
+        assert!(script.contains("INSERT INTO MY_TABLE (Flag) VALUES\n    (TRUE),\n    (FALSE);"), "{}", script);
+    }
+}
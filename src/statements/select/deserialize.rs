@@ -0,0 +1,290 @@
+use std::{fmt::{self, Display}, iter::Zip, slice::Iter, vec::IntoIter};
+use serde::{de::{self, value::SeqDeserializer, DeserializeSeed, IntoDeserializer, MapAccess, Visitor}, forward_to_deserialize_any, Deserializer};
+
+#[cfg(feature = "decimal")]
+use rust_decimal::prelude::ToPrimitive;
+
+use crate::{format_data::FormattedData, types::BooleanStorage, utils::to_hex};
+
+#[derive(Debug)]
+pub(crate) struct DeserializeError(String);
+
+impl Display for DeserializeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result { f.write_str(&self.0) }
+}
+
+impl std::error::Error for DeserializeError {}
+
+impl de::Error for DeserializeError {
+    fn custom<T: Display>(msg: T) -> Self { DeserializeError(msg.to_string()) }
+
+    fn missing_field(field: &'static str) -> Self {
+        DeserializeError(format!("missing field `{}`, none of the selected columns match it", field))
+    }
+}
+
+/// Deserializes a row as a map of the selected column names to their values
+pub(crate) struct RowDeserializer<'a> {
+    pub header: &'a [String],
+    pub row: Vec<FormattedData>,
+}
+
+impl<'de> Deserializer<'de> for RowDeserializer<'_> {
+    type Error = DeserializeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_map(RowAccess {
+            cells: self.header.iter().zip(self.row),
+            current_column: None,
+        })
+    }
+
+    // tuples are filled in the same order as the selected columns
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        let mut seq = SeqDeserializer::new(self.row.into_iter().map(CellDeserializer));
+        let value = visitor.visit_seq(&mut seq)?;
+        seq.end()?;
+        Ok(value)
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(self, _name: &'static str, _len: usize, visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct map struct enum identifier ignored_any
+    }
+}
+
+struct RowAccess<'a> {
+    cells: Zip<Iter<'a, String>, IntoIter<FormattedData>>,
+    current_column: Option<(&'a String, FormattedData)>,
+}
+
+impl<'de> MapAccess<'de> for RowAccess<'_> {
+    type Error = DeserializeError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error> {
+        match self.cells.next() {
+            Some((column, cell)) => {
+                self.current_column = Some((column, cell));
+                seed.deserialize(IntoDeserializer::<DeserializeError>::into_deserializer(column.as_str())).map(Some)
+            },
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Self::Error> {
+        match self.current_column.take() {
+            // adds the column name to the error so it's clear which field had the wrong type
+            Some((column, cell)) => seed.deserialize(CellDeserializer(cell))
+                .map_err(|e| DeserializeError(format!("column `{}`: {}", column, e))),
+            None => Err(de::Error::custom("value requested before its column name")),
+        }
+    }
+}
+
+/// Deserializes a single cell from a row
+pub(crate) struct CellDeserializer(FormattedData);
+
+impl<'de> IntoDeserializer<'de, DeserializeError> for CellDeserializer {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self::Deserializer { self }
+}
+
+// whole numbers are sometimes returned as floats (e.g. a NUMBER column without a precision), 
+// so they're allowed to fill integer fields as long as there's nothing after the decimal point
+macro_rules! deserialize_integer {
+    ($method:ident) => {
+        fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+            match self.0 {
+                FormattedData::FLOAT(val) if val.fract() == 0.0 => visitor.visit_i64(val as i64),
+                #[cfg(feature = "decimal")]
+                FormattedData::DECIMAL(val) if val.fract().is_zero() => match val.to_i64() {
+                    Some(int) => visitor.visit_i64(int),
+                    None => visitor.visit_string(val.to_string()),
+                },
+                _ => self.deserialize_any(visitor),
+            }
+        }
+    };
+}
+
+// decimals can fill float fields, rounded to the nearest float
+#[cfg(feature = "decimal")]
+macro_rules! deserialize_float {
+    ($method:ident) => {
+        fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+            match self.0 {
+                FormattedData::DECIMAL(val) => match val.to_f64() {
+                    Some(float) => visitor.visit_f64(float),
+                    None => visitor.visit_string(val.to_string()),
+                },
+                _ => self.deserialize_any(visitor),
+            }
+        }
+    };
+}
+
+impl<'de> Deserializer<'de> for CellDeserializer {
+    type Error = DeserializeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.0 {
+            FormattedData::STRING(val) => visitor.visit_string(val),
+            FormattedData::INT(val) => visitor.visit_i64(val),
+            FormattedData::FLOAT(val) => visitor.visit_f64(val),
+            FormattedData::DATE(val) => visitor.visit_string(val.to_string()),
+            // matches the format chrono uses to deserialize a NaiveDateTime
+            FormattedData::TIMESTAMP(val) => visitor.visit_string(val.format("%Y-%m-%dT%H:%M:%S%.f").to_string()),
+            // chrono deserializes a DateTime from RFC 3339
+            FormattedData::TIMESTAMPTZ(val) => visitor.visit_string(val.to_rfc3339()),
+            FormattedData::BINARY(val) => visitor.visit_byte_buf(val),
+            FormattedData::BOOLEAN(val) => visitor.visit_bool(val),
+            #[cfg(feature = "decimal")]
+            FormattedData::DECIMAL(val) => visitor.visit_string(val.to_string()),
+            FormattedData::EMPTY => visitor.visit_none(),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.0 {
+            FormattedData::EMPTY => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    // numbers can fill String fields
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.0 {
+            FormattedData::INT(val) => visitor.visit_string(val.to_string()),
+            FormattedData::FLOAT(val) => visitor.visit_string(val.to_string()),
+            FormattedData::BINARY(val) => visitor.visit_string(to_hex(&val)),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    // booleans stored without a BOOLEAN type come back as 1 or 0, or 'Y' or 'N'
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        // the field asks for a bool, so either way it could have been stored is read back
+        match BooleanStorage::Number.load(&self.0).or_else(|| BooleanStorage::YesNo.load(&self.0)) {
+            Some(val) => visitor.visit_bool(val),
+            None => self.deserialize_any(visitor),
+        }
+    }
+
+    // a Vec<u8> field is deserialized as a sequence, so the bytes are handed over one at a time
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.0 {
+            FormattedData::BINARY(val) => visitor.visit_seq(val.into_deserializer()),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_string(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    // unit variants of an enum can be matched by name from a string column
+    fn deserialize_enum<V: Visitor<'de>>(self, _name: &'static str, _variants: &'static [&'static str], visitor: V) -> Result<V::Value, Self::Error> {
+        match self.0 {
+            FormattedData::STRING(val) => visitor.visit_enum(val.into_deserializer()),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    deserialize_integer!(deserialize_i8);
+    deserialize_integer!(deserialize_i16);
+    deserialize_integer!(deserialize_i32);
+    deserialize_integer!(deserialize_i64);
+    deserialize_integer!(deserialize_u8);
+    deserialize_integer!(deserialize_u16);
+    deserialize_integer!(deserialize_u32);
+    deserialize_integer!(deserialize_u64);
+    #[cfg(feature = "decimal")]
+    deserialize_float!(deserialize_f32);
+    #[cfg(feature = "decimal")]
+    deserialize_float!(deserialize_f64);
+
+    #[cfg(not(feature = "decimal"))]
+    forward_to_deserialize_any! { f32 f64 }
+
+    forward_to_deserialize_any! {
+        i128 u128 char bytes byte_buf unit unit_struct 
+        tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, NaiveDateTime};
+    use serde::Deserialize;
+
+    use crate::format_data::FormattedData;
+    use super::RowDeserializer;
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Shift {
+        name: String,
+        starts: NaiveDateTime,
+        ends: Option<NaiveDateTime>,
+    }
+
+    fn header() -> Vec<String> {
+        vec!["name".to_string(), "starts".to_string(), "ends".to_string()]
+    }
+
+    #[test]
+    fn midnight_timestamps_fill_datetime_fields() {
+        let midnight = NaiveDate::from_ymd_opt(2024, 1, 31).unwrap().and_hms_opt(0, 0, 0).unwrap();
+        let header = header();
+        let row = vec![FormattedData::STRING("Night".to_string()), FormattedData::TIMESTAMP(midnight), FormattedData::EMPTY];
+        let shift = Shift::deserialize(RowDeserializer { header: &header, row }).unwrap();
+        assert_eq!(shift, Shift { name: "Night".to_string(), starts: midnight, ends: None });
+    }
+
+    #[test]
+    fn timestamps_with_fractions_fill_datetime_fields() {
+        let starts = NaiveDate::from_ymd_opt(2024, 1, 31).unwrap().and_hms_micro_opt(8, 30, 0, 250).unwrap();
+        let header = header();
+        let row = vec![FormattedData::STRING("Day".to_string()), FormattedData::TIMESTAMP(starts), FormattedData::TIMESTAMP(starts)];
+        let shift = Shift::deserialize(RowDeserializer { header: &header, row }).unwrap();
+        assert_eq!(shift.ends, Some(starts));
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Flags {
+        active: bool,
+        verified: bool,
+        archived: Option<bool>,
+    }
+
+    fn flags(row: Vec<FormattedData>) -> Result<Flags, super::DeserializeError> {
+        let header = vec!["active".to_string(), "verified".to_string(), "archived".to_string()];
+        Flags::deserialize(RowDeserializer { header: &header, row })
+    }
+
+    #[test]
+    fn stored_booleans_fill_bool_fields() {
+        let row = vec![FormattedData::INT(1), FormattedData::STRING("N".to_string()), FormattedData::BOOLEAN(true)];
+        assert_eq!(flags(row).unwrap(), Flags { active: true, verified: false, archived: Some(true) });
+        let row = vec![FormattedData::INT(0), FormattedData::STRING("Y ".to_string()), FormattedData::EMPTY];
+        assert_eq!(flags(row).unwrap(), Flags { active: false, verified: true, archived: None });
+    }
+
+    #[test]
+    fn other_values_are_not_booleans() {
+        assert!(flags(vec![FormattedData::INT(2), FormattedData::BOOLEAN(true), FormattedData::EMPTY]).is_err());
+        assert!(flags(vec![FormattedData::STRING("yes".to_string()), FormattedData::BOOLEAN(true), FormattedData::EMPTY]).is_err());
+    }
+}
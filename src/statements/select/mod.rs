@@ -1,9 +1,13 @@
-use utils::get_header_and_query;
+use deserialize::RowDeserializer;
+use oracle::sql_type::{OracleType, ToSql};
+use serde::de::DeserializeOwned;
+use utils::{get_header_and_query, get_query_and_binds, get_typed_cell};
 
-use crate::types::{errors::OracleSqlToolsError, ClauseType};
+use crate::{format_data::{FormatData, FormattedData}, types::{errors::OracleSqlToolsError, BooleanStorage, ClauseType, Condition, Dialect, Filter, Operator}};
 use super::PreppedRowData;
 
-mod utils;
+mod deserialize;
+pub(crate) mod utils;
 
 impl PreppedRowData {
     /// Selects the columns (via the input vector) from the specified table.
@@ -29,41 +33,111 @@ impl PreppedRowData {
         self
     }
 
-    pub fn filter(mut self, clause: ClauseType, column: &str, value: &str) -> Self {
-        let ty = match clause {
-            ClauseType::Where => "WHERE",
-            ClauseType::And => "AND",
-            ClauseType::Or => "OR"
-        };
+    /// Adds a condition to the query, e.g. `WHERE column = :1`
+    ///
+    /// The first filter always starts the `WHERE` clause, the [`ClauseType`] decides how each following filter is joined to the ones before it.
+    ///
+    /// The value is sent to the database as a bind parameter rather than being written into the SQL, so it doesn't need to be escaped. 
+    /// Numbers and dates are bound as their native Oracle types.
+    /// ```no_run
+    /// let table_data: Vec<Vec<Option<String>>> = col_names.prep_data(conn)
+    ///     .select("MY_TABLE")
+    ///     .filter(ClauseType::Where, "Name", "O'Brien")
+    ///     .filter(ClauseType::And, "Employee ID", 1234)
+    ///     .execute()?;
+    /// ```
+    pub fn filter<T: FormatData>(self, clause: ClauseType, column: &str, value: T) -> Self {
+        self.filter_by(clause, column, Operator::Equal(value.fmt_data()))
+    }
 
-        let stmt = format!("{} {} = '{}'", ty, column, value);
+    /// Adds a condition to the query using any of the comparisons in [`Operator`]
+    ///
+    /// ```no_run
+    /// let table_data: Vec<Vec<Option<String>>> = col_names.prep_data(conn)
+    ///     .select("MY_TABLE")
+    ///     .filter_by(ClauseType::Where, "Department", Operator::In(vec!["Sales".fmt_data(), "Finance".fmt_data()]))
+    ///     .filter_by(ClauseType::And, "Salary", Operator::Between(50000.fmt_data(), 90000.fmt_data()))
+    ///     .filter_by(ClauseType::And, "Termination Date", Operator::IsNull)
+    ///     .execute()?;
+    /// ```
+    pub fn filter_by(self, clause: ClauseType, column: &str, operator: Operator) -> Self {
+        self.filter_condition(clause, Condition::new(column, operator))
+    }
+
+    /// Adds a [`Condition`] to the query, which can group and nest other conditions
+    ///
+    /// ```no_run
+    /// // WHERE Department = :1 AND (Salary > :2 OR NOT (Job_Title LIKE :3))
+    /// let table_data: Vec<Vec<Option<String>>> = col_names.prep_data(conn)
+    ///     .select("MY_TABLE")
+    ///     .filter(ClauseType::Where, "Department", "Sales")
+    ///     .filter_condition(ClauseType::And, 
+    ///         Condition::new("Salary", Operator::GreaterThan(50000.fmt_data()))
+    ///             .or(Condition::new("Job Title", Operator::Like("%Manager%".fmt_data())).negate())
+    ///     )
+    ///     .execute()?;
+    /// ```
+    pub fn filter_condition(mut self, clause: ClauseType, condition: Condition) -> Self {
+        let filter = Filter { clause, condition };
         match self.filters {
-            Some(ref mut val) => val.push(stmt),
-            None => self.filters = Some(vec![stmt]),
+            Some(ref mut val) => val.push(filter),
+            None => self.filters = Some(vec![filter]),
         };
         self
     }
 
-    pub fn execute(self) -> Result<Vec<Vec<Option<String>>>, OracleSqlToolsError> {
-        let header = match self.header {
-            Some(val) => val,
-            None => return Err(OracleSqlToolsError::SQLQueryEmpty),
-        };
+    /// Returns at most this many rows
+    ///
+    /// Written as `FETCH FIRST n ROWS ONLY`, or with `ROWNUM` on Oracle 11g (see [`.with_dialect()`](PreppedRowData::with_dialect)).
+    /// ```no_run
+    /// let table_data: Vec<Vec<Option<String>>> = col_names.prep_data(conn)
+    ///     .select("MY_TABLE")
+    ///     .filter(ClauseType::Where, "Department", "Sales")
+    ///     .limit(100)
+    ///     .execute()?;
+    /// ```
+    pub fn limit(mut self, rows: usize) -> Self {
+        self.limit = Some(rows);
+        self
+    }
+
+    /// Sets the Oracle version the query is written for, instead of detecting it from the connection
+    ///
+    /// ```no_run
+    /// let table_data: Vec<Vec<Option<String>>> = col_names.prep_data(conn)
+    ///     .with_dialect(Dialect::Oracle11g)
+    ///     .select("MY_TABLE")
+    ///     .limit(100)
+    ///     .execute()?;
+    /// ```
+    pub fn with_dialect(mut self, dialect: Dialect) -> Self {
+        self.dialect = Some(dialect);
+        self
+    }
+
+    /// Sets how booleans are stored in the table, so a boolean filter value is compared the same way it was inserted
+    ///
+    /// Only used before 23ai, which has a `BOOLEAN` type. Defaults to [`BooleanStorage::Number`].
+    /// ```no_run
+    /// let table_data: Vec<Vec<Option<String>>> = col_names.prep_data(conn)
+    ///     .with_boolean_storage(BooleanStorage::YesNo)
+    ///     .select("MY_TABLE")
+    ///     .filter(ClauseType::Where, "Active", true)
+    ///     .execute()?;
+    /// ```
+    pub fn with_boolean_storage(mut self, boolean_storage: BooleanStorage) -> Self {
+        self.boolean_storage = boolean_storage;
+        self
+    }
 
-        let mut sql = match self.query {
+    pub fn execute(self) -> Result<Vec<Vec<Option<String>>>, OracleSqlToolsError> {
+        let header = match &self.header {
             Some(val) => val,
             None => return Err(OracleSqlToolsError::SQLQueryEmpty),
         };
 
-        match self.filters {
-            Some(filters) => {
-                let clauses = filters.join(" ");
-                sql = format!("{} {}", sql, clauses);
-            },
-            None => sql = sql,
-        }
-
-        let query = self.conn.query(&sql, &[])?;
+        let (sql, bind_values) = get_query_and_binds(&self)?;
+        let query = self.conn.query(&sql, &bind_values.iter().map(|value| value as &dyn ToSql).collect::<Vec<&dyn ToSql>>())?;
         let mut outer_vec = Vec::new();
         for v in query {
             let p = v?;
@@ -77,4 +151,92 @@ impl PreppedRowData {
 
         Ok(outer_vec)
     }
-}
\ No newline at end of file
+
+    /// Executes the query, keeping the datatype of each column instead of converting every value to a string
+    ///
+    /// The type of each cell is decided by the column's Oracle datatype: 
+    /// - `NUMBER` columns with a scale of 0 (or no scale) become [`FormattedData::INT`] when the value is a whole number, otherwise [`FormattedData::FLOAT`]
+    /// - `NUMBER` columns with a positive scale, `FLOAT`, `BINARY_FLOAT` and `BINARY_DOUBLE` become [`FormattedData::FLOAT`]
+    /// - With the `decimal` feature, `NUMBER` values with decimals become [`FormattedData::DECIMAL`] instead, so they aren't rounded
+    /// - `DATE` and `TIMESTAMP` columns become [`FormattedData::TIMESTAMP`], since an Oracle `DATE` always has a time component
+    /// - `TIMESTAMP WITH TIME ZONE` and `TIMESTAMP WITH LOCAL TIME ZONE` columns become [`FormattedData::TIMESTAMPTZ`]
+    /// - `RAW`, `LONG RAW` and `BLOB` columns become [`FormattedData::BINARY`]
+    /// - `BOOLEAN` columns become [`FormattedData::BOOLEAN`]. Booleans stored as `NUMBER(1)` or `CHAR(1)` before 23ai come back as [`FormattedData::INT`] or [`FormattedData::STRING`], 
+    ///   since nothing in the column type says they're booleans. Read them with [`BooleanStorage::load`], or select into a struct with a `bool` field using [`.execute_as()`](PreppedRowData::execute_as)
+    /// - `NULL` values become [`FormattedData::EMPTY`]
+    /// - Everything else becomes [`FormattedData::STRING`], `CLOB` columns included, which are read in full
+    /// ```no_run
+    /// let table_data: Vec<Vec<FormattedData>> = col_names.prep_data(conn)
+    ///     .select("MY_TABLE")
+    ///     .execute_typed()?;
+    /// ```
+    pub fn execute_typed(self) -> Result<Vec<Vec<FormattedData>>, OracleSqlToolsError> {
+        let (sql, bind_values) = get_query_and_binds(&self)?;
+        let query = self.conn.query(&sql, &bind_values.iter().map(|value| value as &dyn ToSql).collect::<Vec<&dyn ToSql>>())?;
+        let column_types = query.column_info().iter()
+            .map(|column| column.oracle_type().clone())
+            .collect::<Vec<OracleType>>();
+
+        let mut outer_vec = Vec::new();
+        for row_result in query {
+            let row = row_result?;
+            let mut inner_vec = Vec::new();
+            for (colindx, oracle_type) in column_types.iter().enumerate() {
+                inner_vec.push(get_typed_cell(&row, colindx, oracle_type)?)
+            }
+            outer_vec.push(inner_vec)
+        }
+
+        Ok(outer_vec)
+    }
+
+    /// Selects the columns from the specified table and deserializes each row into `T`
+    ///
+    /// Shorthand for `.select(table_name).execute_as()`, see [`.execute_as()`](PreppedRowData::execute_as) for how the columns are matched to the struct.
+    /// ```no_run
+    /// let employees: Vec<Employee> = col_names.prep_data(conn).select_as("MY_TABLE")?;
+    /// ```
+    pub fn select_as<T: DeserializeOwned>(self, table_name: &str) -> Result<Vec<T>, OracleSqlToolsError> {
+        self.select(table_name).execute_as()
+    }
+
+    /// Executes the query and deserializes each row into `T`
+    ///
+    /// The struct's fields are matched to the selected column names, with the same invalid characters removed as in the query (e.g. `"Employee ID"` becomes `Employee_ID`). 
+    /// Use `#[serde(rename = "...")]` or `#[serde(rename_all = "...")]` if they're named differently.
+    ///
+    /// The values are read with [`.execute_typed()`](PreppedRowData::execute_typed), so numbers and dates don't have to be parsed from strings. 
+    /// Use an `Option<>` field for any column that can be `NULL`, and a `NaiveDateTime` field for a `DATE` column.
+    /// ```no_run
+    /// #[derive(serde::Deserialize)]
+    /// struct Employee {
+    ///     #[serde(rename = "Employee_ID")]
+    ///     id: i64,
+    ///     #[serde(rename = "Name")]
+    ///     name: String,
+    ///     #[serde(rename = "Department")]
+    ///     department: Option<String>,
+    /// }
+    ///
+    /// let col_names: Vec<&str> = vec!["Employee ID", "Name", "Department"];
+    ///
+    /// let employees: Vec<Employee> = col_names.prep_data(conn)
+    ///     .select("MY_TABLE")
+    ///     .filter(ClauseType::Where, "Business Unit", "Retail")
+    ///     .execute_as()?;
+    /// ```
+    pub fn execute_as<T: DeserializeOwned>(self) -> Result<Vec<T>, OracleSqlToolsError> {
+        let header = match &self.header {
+            Some(val) => val.to_owned(),
+            None => return Err(OracleSqlToolsError::SQLQueryEmpty),
+        };
+
+        self.execute_typed()?.into_iter().enumerate().map(|(row_index, row)| {
+            T::deserialize(RowDeserializer { header: &header, row })
+                .map_err(|e| OracleSqlToolsError::DeserializeError { 
+                    error_message: e.to_string(), 
+                    row_index,
+                })
+        }).collect()
+    }
+}
@@ -1,4 +1,7 @@
-use crate::statements::{utils::remove_invalid_chars, PreppedRowData};
+use chrono::{DateTime, FixedOffset, NaiveDateTime};
+use oracle::{sql_type::OracleType, Row};
+
+use crate::{format_data::FormattedData, statements::{utils::remove_invalid_chars, PreppedRowData}, types::{errors::OracleSqlToolsError, BooleanStorage, ClauseType, Condition, Dialect, Filter, Operator}};
 
 pub fn get_header_and_query(input: &PreppedRowData, table_name: &str) -> (Vec<String>, String) {
     let header = input.data.iter().map(|cell|
@@ -6,4 +9,381 @@ pub fn get_header_and_query(input: &PreppedRowData, table_name: &str) -> (Vec<St
     ).collect::<Vec<String>>();
     let query = format!("SELECT {} FROM {}", &header.join(", "), table_name);
     (header, query)
-}
\ No newline at end of file
+}
+
+/// Joins the select statement with its filter clauses and collects the values to bind to them
+pub fn get_query_and_binds(input: &PreppedRowData) -> Result<(String, Vec<FormattedData>), OracleSqlToolsError> {
+    let mut sql = match &input.query {
+        Some(val) => val.to_owned(),
+        None => return Err(OracleSqlToolsError::SQLQueryEmpty),
+    };
+
+    let mut bind_values: Vec<FormattedData> = Vec::new();
+    if let Some(filters) = &input.filters {
+        let (clauses, values) = get_filter_clauses(filters);
+        sql = format!("{} {}", sql, clauses);
+        bind_values = stored_bind_values(values, input.boolean_storage, || Ok(Dialect::resolve(input.dialect, &input.conn)?.supports_boolean()))?;
+    }
+    if let Some(limit) = input.limit {
+        sql = Dialect::resolve(input.dialect, &input.conn)?.limit_rows(&sql, limit);
+    }
+    Ok((sql, bind_values))
+}
+
+/// Swaps boolean filter values for the values they're stored as when the database doesn't have a `BOOLEAN` type, the same way they're inserted
+///
+/// `supports_boolean` is only called if there's a boolean to bind, so the dialect isn't detected for nothing
+pub(crate) fn stored_bind_values<F>(values: Vec<&FormattedData>, boolean_storage: BooleanStorage, supports_boolean: F) -> Result<Vec<FormattedData>, OracleSqlToolsError> 
+where F: FnOnce() -> Result<bool, OracleSqlToolsError> {
+    if !values.iter().any(|value| matches!(value, FormattedData::BOOLEAN(_))) {
+        return Ok(values.into_iter().cloned().collect())
+    }
+    let supports_boolean = supports_boolean()?;
+    Ok(values.into_iter().map(|value| match value {
+        FormattedData::BOOLEAN(val) if !supports_boolean => boolean_storage.store(*val),
+        value => value.to_owned(),
+    }).collect())
+}
+
+/// Fetches a cell as the [`FormattedData`] variant that matches the column's Oracle datatype
+pub fn get_typed_cell(row: &Row, colindx: usize, oracle_type: &OracleType) -> Result<FormattedData, OracleSqlToolsError> {
+    let cell = match oracle_type {
+        #[cfg(not(feature = "decimal"))]
+        OracleType::Number(_, scale) if *scale > 0 => row.get::<usize, Option<f64>>(colindx)?
+            .map(FormattedData::FLOAT),
+        #[cfg(feature = "decimal")]
+        OracleType::Number(_, scale) if *scale > 0 => row.get::<usize, Option<String>>(colindx)?
+            .map(|val| match val.parse::<rust_decimal::Decimal>() {
+                Ok(decimal) => FormattedData::DECIMAL(decimal),
+                Err(_) => parse_number(val),
+            }),
+        // a NUMBER without a positive scale can still hold decimals (e.g. NUMBER with no precision or scale), 
+        // so the value is read as text to check if it's a whole number without losing precision
+        OracleType::Number(_, _) | OracleType::Int64 | OracleType::UInt64 => row.get::<usize, Option<String>>(colindx)?
+            .map(parse_number),
+        OracleType::Float(_) | OracleType::BinaryFloat | OracleType::BinaryDouble => row.get::<usize, Option<f64>>(colindx)?
+            .map(FormattedData::FLOAT),
+        // a DATE always holds a time, so every value in the column comes back the same way, midnight included
+        OracleType::Date | OracleType::Timestamp(_) => row.get::<usize, Option<NaiveDateTime>>(colindx)?
+            .map(FormattedData::TIMESTAMP),
+        // a LOCAL TIME ZONE value comes back with the session's offset
+        OracleType::TimestampTZ(_) | OracleType::TimestampLTZ(_) => row.get::<usize, Option<DateTime<FixedOffset>>>(colindx)?
+            .map(FormattedData::TIMESTAMPTZ),
+        OracleType::Boolean => row.get::<usize, Option<bool>>(colindx)?
+            .map(FormattedData::BOOLEAN),
+        OracleType::Raw(_) | OracleType::LongRaw | OracleType::BLOB => row.get::<usize, Option<Vec<u8>>>(colindx)?
+            .map(FormattedData::BINARY),
+        _ => row.get::<usize, Option<String>>(colindx)?
+            .map(FormattedData::STRING),
+    };
+    Ok(cell.unwrap_or(FormattedData::EMPTY))
+}
+
+/// Reads the text of a number as a whole number if it is one, otherwise as a decimal (with the `decimal` feature) or a float
+fn parse_number(val: String) -> FormattedData {
+    if let Ok(int) = val.parse::<i64>() { return FormattedData::INT(int) }
+    #[cfg(feature = "decimal")]
+    if let Ok(decimal) = val.parse::<rust_decimal::Decimal>() { return FormattedData::DECIMAL(decimal) }
+    match val.parse::<f64>() {
+        Ok(float) => FormattedData::FLOAT(float),
+        Err(_) => FormattedData::STRING(val),
+    }
+}
+
+/// Builds the filter clauses and collects the values to bind to their numbered placeholders (`:1`, `:2`, ...)
+pub fn get_filter_clauses(filters: &[Filter]) -> (String, Vec<&FormattedData>) {
+    let mut bind_values = Vec::new();
+    let clauses = filters.iter().enumerate().map(|(i, filter)| {
+        // the first filter always opens the WHERE clause, regardless of its ClauseType
+        let ty = match (i, &filter.clause) {
+            (0, _) => "WHERE",
+            (_, ClauseType::Where) | (_, ClauseType::And) => "AND",
+            (_, ClauseType::Or) => "OR"
+        };
+        format!("{} {}", ty, filter.condition.to_sql(&mut bind_values))
+    }).collect::<Vec<String>>().join(" ");
+    (clauses, bind_values)
+}
+
+/// True if the filter clauses narrow the rows by a column
+///
+/// `AND` binds tighter than `OR`, so each run of filters between the `OR`s needs a condition that compares a column.
+pub(crate) fn filters_column(filters: &[Filter]) -> bool {
+    let mut runs: Vec<bool> = Vec::new();
+    for (i, filter) in filters.iter().enumerate() {
+        match (i, &filter.clause, runs.last_mut()) {
+            (0, _, _) | (_, ClauseType::Or, _) | (_, _, None) => runs.push(filter.condition.filters_column()),
+            (_, _, Some(run)) => *run |= filter.condition.filters_column(),
+        }
+    }
+    !runs.is_empty() && runs.into_iter().all(|run| run)
+}
+
+impl Condition {
+    /// Creates a comparison against a single column
+    pub fn new(column: &str, operator: Operator) -> Self {
+        Condition::Compare { column: column.to_string(), operator }
+    }
+
+    /// Combines this condition with another using `AND`
+    pub fn and(self, other: Condition) -> Self {
+        match self {
+            Condition::And(mut conditions) => { conditions.push(other); Condition::And(conditions) },
+            condition => Condition::And(vec![condition, other]),
+        }
+    }
+
+    /// Combines this condition with another using `OR`
+    pub fn or(self, other: Condition) -> Self {
+        match self {
+            Condition::Or(mut conditions) => { conditions.push(other); Condition::Or(conditions) },
+            condition => Condition::Or(vec![condition, other]),
+        }
+    }
+
+    /// Negates this condition with `NOT`
+    pub fn negate(self) -> Self { Condition::Not(Box::new(self)) }
+
+    /// True if the condition narrows the rows by a column, rather than rendering as `1 = 1` or `1 = 0`
+    ///
+    /// An `OR` only narrows the rows if every one of its branches does.
+    pub(crate) fn filters_column(&self) -> bool {
+        self.narrows_rows(false)
+    }
+
+    // `NOT (a AND b)` is `NOT a OR NOT b`, so under a `NOT` the `AND` and `OR` rules swap. 
+    // An empty `IN` or `NOT IN` is constant either way, so negating it never compares a column.
+    fn narrows_rows(&self, negated: bool) -> bool {
+        let all = |conditions: &[Condition]| !conditions.is_empty() && conditions.iter().all(|condition| condition.narrows_rows(negated));
+        let any = |conditions: &[Condition]| conditions.iter().any(|condition| condition.narrows_rows(negated));
+        match self {
+            Condition::Compare { operator: Operator::In(vals) | Operator::NotIn(vals), .. } => !vals.is_empty(),
+            Condition::Compare { .. } => true,
+            Condition::And(conditions) if negated => all(conditions),
+            Condition::And(conditions) => any(conditions),
+            Condition::Or(conditions) if negated => any(conditions),
+            Condition::Or(conditions) => all(conditions),
+            Condition::Not(condition) => condition.narrows_rows(!negated),
+        }
+    }
+
+    pub(crate) fn to_sql<'a>(&'a self, bind_values: &mut Vec<&'a FormattedData>) -> String {
+        match self {
+            Condition::Compare { column, operator } => operator.to_sql(&remove_invalid_chars(column), bind_values),
+            Condition::And(conditions) => group_conditions(conditions, "AND", "1 = 1", bind_values),
+            Condition::Or(conditions) => group_conditions(conditions, "OR", "1 = 0", bind_values),
+            Condition::Not(condition) => format!("NOT ({})", condition.to_sql(bind_values)),
+        }
+    }
+}
+
+fn group_conditions<'a>(conditions: &'a [Condition], joiner: &str, when_empty: &str, bind_values: &mut Vec<&'a FormattedData>) -> String {
+    match conditions.len() {
+        0 => when_empty.to_string(),
+        1 => conditions[0].to_sql(bind_values),
+        _ => {
+            let inner = conditions.iter()
+                .map(|condition| condition.to_sql(bind_values))
+                .collect::<Vec<String>>();
+            format!("({})", inner.join(&format!(" {} ", joiner)))
+        },
+    }
+}
+
+impl Operator {
+    pub(crate) fn to_sql<'a>(&'a self, column: &str, bind_values: &mut Vec<&'a FormattedData>) -> String {
+        let mut bind = |value: &'a FormattedData| -> String {
+            bind_values.push(value);
+            format!(":{}", bind_values.len())
+        };
+        match self {
+            Operator::Equal(val) => format!("{} = {}", column, bind(val)),
+            Operator::NotEqual(val) => format!("{} <> {}", column, bind(val)),
+            Operator::LessThan(val) => format!("{} < {}", column, bind(val)),
+            Operator::LessThanOrEqual(val) => format!("{} <= {}", column, bind(val)),
+            Operator::GreaterThan(val) => format!("{} > {}", column, bind(val)),
+            Operator::GreaterThanOrEqual(val) => format!("{} >= {}", column, bind(val)),
+            Operator::Like(val) => format!("{} LIKE {}", column, bind(val)),
+            Operator::NotLike(val) => format!("{} NOT LIKE {}", column, bind(val)),
+            // an empty list can't be written as `IN ()`, so it's replaced with a condition that's always false (or true for NOT IN)
+            Operator::In(vals) if vals.is_empty() => "1 = 0".to_string(),
+            Operator::NotIn(vals) if vals.is_empty() => "1 = 1".to_string(),
+            Operator::In(vals) => {
+                let placeholders = vals.iter().map(&mut bind).collect::<Vec<String>>();
+                format!("{} IN ({})", column, placeholders.join(", "))
+            },
+            Operator::NotIn(vals) => {
+                let placeholders = vals.iter().map(&mut bind).collect::<Vec<String>>();
+                format!("{} NOT IN ({})", column, placeholders.join(", "))
+            },
+            Operator::Between(low, high) => {
+                let low = bind(low);
+                format!("{} BETWEEN {} AND {}", column, low, bind(high))
+            },
+            Operator::IsNull => format!("{} IS NULL", column),
+            Operator::IsNotNull => format!("{} IS NOT NULL", column),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{format_data::FormattedData, types::{BooleanStorage, ClauseType, Condition, Filter, Operator}};
+    use super::{filters_column, get_filter_clauses, stored_bind_values};
+
+    fn int(val: i64) -> FormattedData { FormattedData::INT(val) }
+
+    fn string(val: &str) -> FormattedData { FormattedData::STRING(val.to_string()) }
+
+    #[test]
+    fn operators_bind_each_value_in_order() {
+        let operators = [
+            Operator::Equal(int(1)),
+            Operator::NotEqual(int(2)),
+            Operator::LessThanOrEqual(int(3)),
+            Operator::NotLike(string("x%")),
+            Operator::In(vec![int(5), int(6)]),
+            Operator::NotIn(vec![int(7)]),
+            Operator::Between(int(8), int(9)),
+        ];
+        let mut binds = Vec::new();
+        let sql = operators.iter().map(|operator| operator.to_sql("a", &mut binds)).collect::<Vec<String>>();
+        assert_eq!(sql, vec![
+            "a = :1", "a <> :2", "a <= :3", "a NOT LIKE :4", "a IN (:5, :6)", "a NOT IN (:7)", "a BETWEEN :8 AND :9",
+        ]);
+        assert_eq!(binds, vec![&int(1), &int(2), &int(3), &string("x%"), &int(5), &int(6), &int(7), &int(8), &int(9)]);
+    }
+
+    #[test]
+    fn operators_without_values_bind_nothing() {
+        let operators = [Operator::IsNull, Operator::IsNotNull, Operator::In(Vec::new()), Operator::NotIn(Vec::new())];
+        let mut binds = Vec::new();
+        let sql = operators.iter().map(|operator| operator.to_sql("a", &mut binds)).collect::<Vec<String>>();
+        assert_eq!(sql, vec!["a IS NULL", "a IS NOT NULL", "1 = 0", "1 = 1"]);
+        assert!(binds.is_empty());
+    }
+
+    #[test]
+    fn nested_groups_are_parenthesized_and_numbered_in_order() {
+        // a = 1 AND (b = 2 OR NOT c LIKE x)
+        let condition = Condition::new("a", Operator::Equal(int(1)))
+            .and(Condition::new("b", Operator::Equal(int(2)))
+                .or(Condition::new("c", Operator::Like(string("x"))).negate()));
+        let mut binds = Vec::new();
+        assert_eq!(condition.to_sql(&mut binds), "(a = :1 AND (b = :2 OR NOT (c LIKE :3)))");
+        assert_eq!(binds, vec![&int(1), &int(2), &string("x")]);
+    }
+
+    #[test]
+    fn groups_number_binds_across_in_and_between() {
+        let condition = Condition::Or(vec![
+            Condition::new("a", Operator::In(vec![int(1), int(2)])),
+            Condition::And(vec![
+                Condition::new("b", Operator::Between(int(3), int(4))),
+                Condition::new("c", Operator::GreaterThan(int(5))),
+            ]),
+        ]);
+        let mut binds = Vec::new();
+        assert_eq!(condition.to_sql(&mut binds), "(a IN (:1, :2) OR (b BETWEEN :3 AND :4 AND c > :5))");
+        assert_eq!(binds, vec![&int(1), &int(2), &int(3), &int(4), &int(5)]);
+    }
+
+    #[test]
+    fn empty_and_single_groups() {
+        let conditions = [
+            Condition::And(Vec::new()),
+            Condition::Or(Vec::new()),
+            Condition::Or(Vec::new()).negate(),
+            Condition::And(vec![Condition::new("a", Operator::IsNull)]),
+        ];
+        let mut binds = Vec::new();
+        let sql = conditions.iter().map(|condition| condition.to_sql(&mut binds)).collect::<Vec<String>>();
+        assert_eq!(sql, vec!["1 = 1", "1 = 0", "NOT (1 = 0)", "a IS NULL"]);
+        assert!(binds.is_empty());
+    }
+
+    #[test]
+    fn column_names_are_cleaned() {
+        let mut binds = Vec::new();
+        assert_eq!(Condition::new("Hire Date", Operator::IsNotNull).to_sql(&mut binds), "Hire_Date IS NOT NULL");
+    }
+
+    #[test]
+    fn filter_clauses_open_with_where_and_continue_the_numbering() {
+        let filters = vec![
+            Filter { clause: ClauseType::Or, condition: Condition::new("a", Operator::Equal(int(1))) },
+            Filter { clause: ClauseType::Where, condition: Condition::new("b", Operator::In(vec![int(2), int(3)])) },
+            Filter { 
+                clause: ClauseType::Or, 
+                condition: Condition::new("c", Operator::Equal(int(4))).and(Condition::new("d", Operator::Equal(int(5)))),
+            },
+        ];
+        let (clauses, binds) = get_filter_clauses(&filters);
+        assert_eq!(clauses, "WHERE a = :1 AND b IN (:2, :3) OR (c = :4 AND d = :5)");
+        assert_eq!(binds, vec![&int(1), &int(2), &int(3), &int(4), &int(5)]);
+    }
+
+    #[test]
+    fn filters_without_a_column_comparison() {
+        let filter = |clause: ClauseType, condition: Condition| Filter { clause, condition };
+        assert!(!filters_column(&[]));
+        assert!(!filters_column(&[filter(ClauseType::Where, Condition::new("a", Operator::NotIn(Vec::new())))]));
+        assert!(!filters_column(&[filter(ClauseType::Where, Condition::And(Vec::new()))]));
+        assert!(!filters_column(&[filter(ClauseType::Where, Condition::new("a", Operator::In(Vec::new())).negate())]));
+        assert!(!filters_column(&[filter(ClauseType::Where, Condition::Or(vec![
+            Condition::new("a", Operator::NotIn(Vec::new())),
+            Condition::new("b", Operator::IsNull),
+        ]))]));
+        assert!(!filters_column(&[
+            filter(ClauseType::Where, Condition::new("a", Operator::IsNull)),
+            filter(ClauseType::Or, Condition::new("b", Operator::NotIn(Vec::new()))),
+        ]));
+    }
+
+    #[test]
+    fn negated_groups_swap_and_with_or() {
+        let filter = |condition: Condition| [Filter { clause: ClauseType::Where, condition }];
+        let equal = || Condition::new("a", Operator::Equal(int(1)));
+        // NOT (a = 1 AND 1 = 0) keeps every row
+        assert!(!filters_column(&filter(equal().and(Condition::new("b", Operator::In(Vec::new()))).negate())));
+        assert!(!filters_column(&filter(Condition::new("b", Operator::NotIn(Vec::new())).negate())));
+        assert!(!filters_column(&filter(Condition::And(Vec::new()).negate())));
+        assert!(filters_column(&filter(equal().and(Condition::new("b", Operator::IsNull)).negate())));
+        assert!(filters_column(&filter(equal().or(Condition::new("b", Operator::In(Vec::new()))).negate())));
+        // a double negative is the group itself
+        assert!(filters_column(&filter(equal().and(Condition::new("b", Operator::In(Vec::new()))).negate().negate())));
+    }
+
+    #[test]
+    fn filters_with_a_column_comparison() {
+        let filter = |clause: ClauseType, condition: Condition| Filter { clause, condition };
+        assert!(filters_column(&[filter(ClauseType::Where, Condition::new("a", Operator::IsNull))]));
+        assert!(filters_column(&[filter(ClauseType::Where, Condition::new("a", Operator::Equal(int(1))).negate())]));
+        assert!(filters_column(&[
+            filter(ClauseType::Where, Condition::new("a", Operator::NotIn(Vec::new()))),
+            filter(ClauseType::And, Condition::new("b", Operator::Equal(int(1)))),
+        ]));
+        assert!(filters_column(&[filter(ClauseType::Where, Condition::And(vec![
+            Condition::Or(Vec::new()),
+            Condition::new("b", Operator::Equal(int(1))),
+        ]))]));
+    }
+
+    #[test]
+    fn boolean_binds_are_stored_without_a_boolean_type() {
+        let (yes, no, one) = (FormattedData::BOOLEAN(true), FormattedData::BOOLEAN(false), int(1));
+        let binds = stored_bind_values(vec![&yes, &no, &one], BooleanStorage::YesNo, || Ok(false)).unwrap();
+        assert_eq!(binds, vec![string("Y"), string("N"), int(1)]);
+        let binds = stored_bind_values(vec![&yes, &no], BooleanStorage::Number, || Ok(false)).unwrap();
+        assert_eq!(binds, vec![int(1), int(0)]);
+        let binds = stored_bind_values(vec![&yes], BooleanStorage::YesNo, || Ok(true)).unwrap();
+        assert_eq!(binds, vec![yes.clone()]);
+    }
+
+    #[test]
+    fn binds_without_booleans_skip_the_dialect() {
+        let one = int(1);
+        let binds = stored_bind_values(vec![&one], BooleanStorage::YesNo, || panic!("the dialect isn't needed")).unwrap();
+        assert_eq!(binds, vec![int(1)]);
+    }
+}
@@ -0,0 +1,124 @@
+use std::{sync::Arc, thread::{self, JoinHandle}};
+use oracle::{Batch, Connection};
+
+use crate::{format_data::FormattedData, progress::ProgressObserver, types::{errors::OracleSqlToolsError, DatatypeIndexes, Dialect, GridProperties, InsertOptions, UpdateReport}};
+use super::{insert::utils::{batch_rows, split_data, start_progress}, mutate_grid::MutateGrid, mutate_row::MutateRow, PreppedGridData};
+
+impl PreppedGridData {
+    /// Updates the rows in an existing table, matching each input row to the table by the key columns
+    ///
+    /// Every column in the header that isn't a key is set to the value in the input row. The rows are split between threads and batched the same way as [`.insert()`](`crate::statements::insert::PreppedGridData::insert`).
+    /// 
+    /// # Usage
+    /// 
+    /// ```no_run
+    /// let conn: oracle::Connection = match Connection::connect("<USERNAME>", "<PASSWORD>", "<IP ADDRESS>")?; 
+    /// 
+    /// let data: Vec<Vec<String>> = vec![
+    ///     vec!["ID".to_string(), "ColB".to_string(), "ColC".to_string()],
+    ///     vec!["1".to_string(), "B1".to_string(), "C1".to_string()],
+    ///     vec!["2".to_string(), "B2".to_string(), "C2".to_string()],
+    /// ];
+    /// 
+    /// let (res, report): (Arc<Connection>, UpdateReport) = data.prep_data(conn).update("MY_TABLE", &["ID"])?;
+    /// println!("{} rows updated, {} keys not found", report.rows_matched, report.keys_not_found.len());
+    /// res.commit()?;
+    /// Ok(())
+    /// ```
+    /// Is the same as running this for each row:
+    /// ```sql
+    /// UPDATE my_table SET ColB = :1, ColC = :2 WHERE ID = :3
+    /// ```
+    /// A key that's empty in the input row is never equal to anything in the table, so that row is counted in [`UpdateReport::keys_not_found`].
+    /// Returns [`OracleSqlToolsError::NoData`] if the grid only has a header.
+    pub fn update(mut self, table_name: &str, key_columns: &[&str]) -> Result<(Arc<Connection>, UpdateReport), OracleSqlToolsError> {
+        if self.data.len() <= 1 { return Err(OracleSqlToolsError::NoData) }
+
+        let (data_header, _) = self.data.separate_header();
+        if let Some(row) = self.data.first() {
+            if data_header.len() != row.len() {
+                return Err(OracleSqlToolsError::InvalidHeaderLength { 
+                    header_length: data_header.len(), 
+                    body_length: row.len(), 
+                })
+            }
+        }
+        let (update_stmt, order) = data_header.update_stmt(table_name, key_columns)?;
+
+        // moves each column to the position of its placeholder in the statement
+        let mut data = self.data.into_iter()
+            .map(|row| order.iter().map(|i| row[*i].to_owned()).collect())
+            .collect::<Vec<Vec<FormattedData>>>();
+        let mut datatype_indexes = self.data_indexes.reorder(&order);
+        if !Dialect::resolve(self.dialect, &self.conn)?.supports_boolean() {
+            self.options.boolean_storage.store_grid(&mut data, &mut datatype_indexes);
+        }
+
+        split_update_by_threads(self.conn, data, update_stmt, datatype_indexes, self.options, self.progress)
+    }
+}
+
+fn split_update_by_threads(
+    conn: Connection, 
+    data: Vec<Vec<FormattedData>>, 
+    update_stmt: String, 
+    datatype_indexes: DatatypeIndexes, 
+    options: InsertOptions,
+    progress: Arc<dyn ProgressObserver>,
+) -> Result<(Arc<Connection>, UpdateReport), OracleSqlToolsError> {
+    let conn: Arc<Connection> = Arc::new(conn);
+    let update_stmt: Arc<String> = Arc::new(update_stmt);
+    let datatype_indexes: Arc<DatatypeIndexes> = Arc::new(datatype_indexes);
+
+    let slices = split_data(data, options.threads);
+    start_progress(progress.as_ref(), &slices);
+
+    let mut handles: Vec<JoinHandle<Result<UpdateReport, OracleSqlToolsError>>> = Vec::new();
+    for (thread, (num, arc_data)) in slices.into_iter().enumerate() {
+        let conn = Arc::clone(&conn);
+        let update = Arc::clone(&update_stmt);
+        let datatype_indexes = Arc::clone(&datatype_indexes);
+        let progress = Arc::clone(&progress);
+        let batch_size = options.batch_size;
+        handles.push(thread::spawn(move || {
+            GridProperties {
+                data: arc_data,
+                num,
+                datatype_indexes,
+                thread,
+                progress,
+            }.update_on_session(&conn, &update, batch_size)
+        }));
+    }
+    let mut report = UpdateReport::default();
+    for handle in handles {
+        report.merge(handle.join().unwrap()?);
+    }
+    progress.finish();
+    Ok((conn, report))
+}
+
+impl GridProperties {
+    fn update_on_session(self, conn: &Connection, update_stmt: &str, batch_size: usize) -> Result<UpdateReport, OracleSqlToolsError> {
+        let batch_size = batch_rows(batch_size, self.data.len());
+        let mut builder = conn.batch(update_stmt, batch_size);
+        builder.with_row_counts();
+        let mut batch: Batch<'_> = builder.build()?;
+        self.set_bind_types(&mut batch)?;
+
+        let mut report = UpdateReport::default();
+        for (chunk_index, chunk) in self.data.chunks(batch_size).enumerate() {
+            let chunk_start = self.num + chunk_index * batch_size;
+            self.bind_chunk(&mut batch, chunk, chunk_start)?;
+            // each chunk is executed as a whole, so the row counts line up with its rows
+            for (offset, count) in batch.row_counts()?.into_iter().enumerate() {
+                match count {
+                    0 => report.keys_not_found.push(chunk_start + offset),
+                    count => report.rows_matched += count,
+                }
+            }
+            self.progress.rows_done(self.thread, chunk.len());
+        }
+        Ok(report)
+    }
+}
@@ -0,0 +1,39 @@
+use std::sync::Arc;
+use oracle::Connection;
+
+use crate::types::errors::OracleSqlToolsError;
+use super::{insert::{stage_insert_data, without_rejected_rows}, mutate_row::MutateRow, PreppedGridData};
+
+impl PreppedGridData {
+    /// Inserts the rows that don't exist in the table yet and updates the ones that do
+    ///
+    /// The rows are matched to the table by the key columns. Each row is bound to a `MERGE` statement, and is split between threads and batched the same way as [`.insert()`](`crate::statements::insert::PreppedGridData::insert`).
+    /// 
+    /// # Usage
+    /// 
+    /// ```no_run
+    /// let conn: oracle::Connection = match Connection::connect("<USERNAME>", "<PASSWORD>", "<IP ADDRESS>")?; 
+    /// 
+    /// let data: Vec<Vec<String>> = vec![
+    ///     vec!["ID".to_string(), "ColB".to_string(), "ColC".to_string()],
+    ///     vec!["1".to_string(), "B1".to_string(), "C1".to_string()],
+    ///     vec!["2".to_string(), "B2".to_string(), "C2".to_string()],
+    /// ];
+    /// 
+    /// let res: Arc<Connection> = data.prep_data(conn).upsert("MY_TABLE", &["ID"])?;
+    /// res.commit()?;
+    /// Ok(())
+    /// ```
+    /// Is the same as running this for each row:
+    /// ```sql
+    /// MERGE INTO my_table t 
+    /// USING (SELECT :1 AS ID, :2 AS ColB, :3 AS ColC FROM dual) s 
+    /// ON (t.ID = s.ID) 
+    /// WHEN MATCHED THEN UPDATE SET t.ColB = s.ColB, t.ColC = s.ColC 
+    /// WHEN NOT MATCHED THEN INSERT (ID, ColB, ColC) VALUES (s.ID, s.ColB, s.ColC)
+    /// ```
+    pub fn upsert(self, table_name: &str, key_columns: &[&str]) -> Result<Arc<Connection>, OracleSqlToolsError> {
+        let batch_prep = stage_insert_data(self, table_name, |header| header.merge_stmt(table_name, key_columns))?;
+        without_rejected_rows(batch_prep.split_batch_by_threads()?)
+    }
+}
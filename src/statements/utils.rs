@@ -16,6 +16,15 @@ pub fn does_table_exist(conn: &Connection, table_name: &str) -> Result<bool, Ora
     Ok(false)
 }
 
+/// Checks if the database allows `VARCHAR2` and `RAW` columns up to 32767 bytes
+///
+/// Reading `v$parameter` needs a grant most users don't have, so anything but a clear `EXTENDED` counts as `STANDARD`.
+pub(crate) fn has_extended_strings(conn: &Connection) -> bool {
+    conn.query_row_as::<String>("SELECT value FROM v$parameter WHERE name = 'max_string_size'", &[])
+        .map(|value| value.eq_ignore_ascii_case("EXTENDED"))
+        .unwrap_or(false)
+}
+
 /// Removes characters that are invalid in SQL column names
 pub fn remove_invalid_chars(input: &String) -> String {
     input
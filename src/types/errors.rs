@@ -1,5 +1,7 @@
 use thiserror::Error;
 
+use super::Dialect;
+
 #[derive(Error, Debug)]
 pub enum OracleSqlToolsError {
     #[error(transparent)]
@@ -19,9 +21,51 @@ pub enum OracleSqlToolsError {
         y_index: usize,
     },
 
+    #[error(transparent)]
+    IoError(#[from] std::io::Error),
+
     #[error(transparent)]
     DateCantConvertToString(#[from] core::convert::Infallible),
 
+    #[error("{feature} isn't supported by {dialect:?}")]
+    UnsupportedByDialect {
+        feature: String,
+        dialect: Dialect,
+    },
+
     #[error("SQL Query is empty")]
-    SQLQueryEmpty
+    SQLQueryEmpty,
+
+    #[error("A delete needs at least one filter on a column, use `.delete_all()` to delete every row in the table")]
+    NoDeleteCondition,
+
+    #[error("Row {row_index} could not be deserialized: {error_message}")]
+    DeserializeError {
+        error_message: String,
+        row_index: usize,
+    },
+
+    #[error("At least one key column is required")]
+    NoKeyColumns,
+
+    #[error("Key column {column} is not in the header")]
+    InvalidKeyColumn { column: String },
+
+    #[error("Every column is a key column, there are no columns left to update")]
+    NoColumnsToUpdate,
+
+    #[error("{rows_rejected} row(s) were rejected by the database, use `.insert_with_report()` to see which ones")]
+    RowsRejected { rows_rejected: usize },
+
+    #[error("The insert failed and was rolled back, {rows_committed} row(s) had already been committed: {source}")]
+    TransactionRolledBack {
+        rows_committed: usize,
+        source: Box<OracleSqlToolsError>,
+    },
+
+    #[error("Row {row_index} could not be serialized: {error_message}")]
+    SerializeError {
+        error_message: String,
+        row_index: usize,
+    },
 }
\ No newline at end of file
@@ -1,7 +1,7 @@
-use std::sync::Arc;
-use oracle::Connection;
+use std::{sync::Arc, time::Duration};
+use oracle::{Connection, DbError};
 
-use crate::format_data::FormattedData;
+use crate::{format_data::FormattedData, progress::ProgressObserver};
 
 pub mod errors;
 
@@ -11,6 +11,13 @@ pub struct DatatypeIndexes {
     pub is_float: Vec<usize>,
     pub is_int: Vec<usize>,
     pub is_date: Vec<usize>,
+    pub is_timestamp_tz: Vec<usize>,
+    pub is_binary: Vec<usize>,
+    pub is_bool: Vec<usize>,
+    /// Only filled with the `decimal` feature
+    pub is_decimal: Vec<usize>,
+    /// `VARCHAR2` columns with a value too long to bind as a string, which are bound as `CLOB`s instead
+    pub is_clob: Vec<usize>,
 }
 
 #[derive(Debug)]
@@ -19,6 +26,152 @@ pub struct BatchPrep {
     pub conn: Connection,
     pub insert_stmt: String,
     pub data_indexes: DatatypeIndexes,
+    pub options: InsertOptions,
+    pub progress: Arc<dyn ProgressObserver>,
+    /// The parts of the report that are known before any rows are inserted
+    pub report: InsertReport,
+}
+
+/// Controls how the data is split up when it's inserted
+///
+/// ```no_run
+/// let options = InsertOptions { threads: 4, batch_size: 5_000, ..Default::default() };
+/// ```
+#[derive(Debug, Clone)]
+pub struct InsertOptions {
+    /// The number of threads the data is split between, defaults to the number of CPU threads on the host machine
+    pub threads: usize,
+    /// The maximum number of rows each thread binds to its [`oracle::Batch`] before executing it, defaults to 10,000
+    pub batch_size: usize,
+    /// Keeps inserting the good rows when the database rejects some of them, collecting the rejected rows into the [`InsertReport`]. 
+    /// Defaults to false, which fails the whole insert on the first bad row
+    pub batch_errors: bool,
+    /// Commits after every this many rows, for loads too large to hold in one transaction. 
+    /// Only used by the inserts that commit for you, defaults to `None` which commits once at the end
+    pub commit_every: Option<usize>,
+    /// Adds an auto-incrementing primary key column with this name when the table is created, needs Oracle 12c or later. Defaults to `None`
+    pub identity_column: Option<String>,
+    /// How booleans are stored on versions before 23ai, which don't have a `BOOLEAN` type. Defaults to [`BooleanStorage::Number`]
+    pub boolean_storage: BooleanStorage,
+    /// The column type timestamps with an offset are created as. Defaults to [`TimeZoneStorage::WithTimeZone`]
+    pub time_zone_storage: TimeZoneStorage,
+    /// Allows `VARCHAR2` and `RAW` columns up to 32767 bytes on 12c or later, for databases with `MAX_STRING_SIZE = EXTENDED`. 
+    /// When it's false, the setting is read from `v$parameter` and assumed to be `STANDARD` (4000 bytes) if that can't be read. Defaults to false
+    pub extended_strings: bool,
+}
+
+/// The column type [`FormattedData::TIMESTAMPTZ`] values are created as
+///
+/// ```no_run
+/// let options = InsertOptions { time_zone_storage: TimeZoneStorage::WithLocalTimeZone, ..Default::default() };
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TimeZoneStorage {
+    /// `TIMESTAMP WITH TIME ZONE`, which stores each value's offset
+    #[default]
+    WithTimeZone,
+    /// `TIMESTAMP WITH LOCAL TIME ZONE`, which converts each value to the database's time zone and shows it in the session's
+    WithLocalTimeZone,
+}
+
+/// The column type booleans are stored in when the database doesn't have a `BOOLEAN` type
+///
+/// ```no_run
+/// let options = InsertOptions { boolean_storage: BooleanStorage::YesNo, ..Default::default() };
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BooleanStorage {
+    /// `NUMBER(1)` holding 1 or 0
+    #[default]
+    Number,
+    /// `CHAR(1)` holding `'Y'` or `'N'`
+    YesNo,
+}
+
+/// A summary of the rows that were inserted
+#[derive(Debug, Default)]
+pub struct InsertReport {
+    pub rows_inserted: usize,
+    /// Only counted by the inserts that commit for you
+    pub rows_committed: usize,
+    /// Only collected when [`InsertOptions::batch_errors`] is enabled
+    pub rejected_rows: Vec<RejectedRow>,
+    /// How many rows each thread was given and how long it took, in thread order
+    pub threads: Vec<ThreadReport>,
+    /// True if the table didn't exist and was created from the input data
+    pub table_created: bool,
+    /// The `CREATE TABLE` statement, if the table was created
+    pub create_table_stmt: Option<String>,
+    /// The statement each row was bound to
+    pub insert_stmt: String,
+    /// Each column name with the Oracle datatype inferred from the input data
+    pub column_types: Vec<(String, String)>,
+}
+
+/// What an insert would run, see [`PreppedGridData::dry_run()`](crate::statements::PreppedGridData::dry_run)
+#[derive(Debug)]
+pub struct DryRunReport {
+    pub dialect: Dialect,
+    pub table_exists: bool,
+    /// The `CREATE TABLE` statement, if the table doesn't exist yet
+    pub create_table_stmt: Option<String>,
+    /// The statement each row would be bound to
+    pub insert_stmt: String,
+    /// Each column name with the Oracle datatype inferred from the input data
+    pub column_types: Vec<(String, String)>,
+}
+
+/// The Oracle version the generated SQL is written for
+///
+/// If it isn't set with `.with_dialect()`, it's detected from [`Connection::server_version()`](oracle::Connection::server_version) when it's needed.
+/// ```no_run
+/// let res: Arc<Connection> = data.prep_data(conn).with_dialect(Dialect::Oracle11g).insert("MY_TABLE")?;
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Dialect {
+    /// Limits rows with `ROWNUM`, `VARCHAR2` columns hold up to 4000 bytes and `RAW` columns up to 2000, no identity columns
+    Oracle11g,
+    /// Adds `FETCH FIRST`, identity columns, and `VARCHAR2` columns up to 32767 bytes when `MAX_STRING_SIZE` is `EXTENDED`
+    Oracle12c,
+    Oracle19c,
+    /// Adds the `BOOLEAN` type and multi-row `VALUES` inserts
+    Oracle23ai,
+}
+
+/// The rows a single thread inserted
+#[derive(Debug)]
+pub struct ThreadReport {
+    pub thread: usize,
+    /// The number of rows the thread was given
+    pub rows: usize,
+    pub rows_inserted: usize,
+    pub duration: Duration,
+}
+
+/// A row the database refused to insert
+#[derive(Debug)]
+pub struct RejectedRow {
+    /// The index of the row in the input data, not counting the header
+    pub row_index: usize,
+    pub values: Vec<FormattedData>,
+    pub error: DbError,
+}
+
+/// A summary of the rows that were updated
+#[derive(Debug, Default)]
+pub struct UpdateReport {
+    /// The number of rows in the table that were updated, which can be more than the number of input rows if the keys aren't unique
+    pub rows_matched: u64,
+    /// The indexes of the input rows (not counting the header) whose keys didn't match any rows in the table
+    pub keys_not_found: Vec<usize>,
+}
+
+/// Counts the rows executed on a connection since it was last committed
+#[derive(Debug)]
+pub struct CommitProgress {
+    pub commit_every: usize,
+    pub pending: usize,
+    pub committed: usize,
 }
 
 #[derive(Debug)]
@@ -26,6 +179,8 @@ pub struct GridProperties {
     pub data: Arc<Vec<Vec<FormattedData>>>,
     pub num: usize,
     pub datatype_indexes: Arc<DatatypeIndexes>,
+    pub thread: usize,
+    pub progress: Arc<dyn ProgressObserver>,
 }
 
 #[derive(Debug)]
@@ -36,9 +191,44 @@ pub struct CellProperties<'a> {
     pub y_ind: usize,
 }
 
+#[derive(Debug)]
+pub struct Filter {
+    pub clause: ClauseType,
+    pub condition: Condition,
+}
+
 #[derive(Debug)]
 pub enum ClauseType {
     Where,
     And,
     Or
+}
+
+/// A single comparison or a group of conditions, which is rendered inside parentheses
+#[derive(Debug)]
+pub enum Condition {
+    Compare { column: String, operator: Operator },
+    And(Vec<Condition>),
+    Or(Vec<Condition>),
+    Not(Box<Condition>),
+}
+
+/// The comparison applied to a column in a filter
+///
+/// Values are bound to the query as parameters, so they never need to be quoted or escaped
+#[derive(Debug)]
+pub enum Operator {
+    Equal(FormattedData),
+    NotEqual(FormattedData),
+    LessThan(FormattedData),
+    LessThanOrEqual(FormattedData),
+    GreaterThan(FormattedData),
+    GreaterThanOrEqual(FormattedData),
+    Like(FormattedData),
+    NotLike(FormattedData),
+    In(Vec<FormattedData>),
+    NotIn(Vec<FormattedData>),
+    Between(FormattedData, FormattedData),
+    IsNull,
+    IsNotNull,
 }
\ No newline at end of file
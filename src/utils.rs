@@ -1,6 +1,7 @@
 use itertools::Itertools;
+use oracle::{sql_type::{OracleType, ToSql}, Connection, Result, SqlValue, Version};
 
-use crate::{format_data::FormattedData, types::DatatypeIndexes};
+use crate::{format_data::FormattedData, types::{BooleanStorage, DatatypeIndexes, Dialect, TimeZoneStorage, InsertOptions, InsertReport, UpdateReport}};
 
 impl FormattedData {
     pub fn to_string(self) -> String {
@@ -10,13 +11,137 @@ impl FormattedData {
             FormattedData::FLOAT(val) => val.to_string(),
             FormattedData::DATE(val) => val.to_string(),
             FormattedData::TIMESTAMP(val) => val.to_string(),
+            FormattedData::TIMESTAMPTZ(val) => val.to_string(),
+            FormattedData::BINARY(val) => to_hex(&val),
+            FormattedData::BOOLEAN(val) => val.to_string(),
+            #[cfg(feature = "decimal")]
+            FormattedData::DECIMAL(val) => val.to_string(),
             FormattedData::EMPTY => "".to_string(),
         }
     }
 }
 
+/// Strings longer than this can't be bound as a `VARCHAR2` unless the database uses extended string sizes
+pub(crate) const MAX_STRING_BIND_LENGTH: usize = 4000;
+
+/// Bytes longer than this can't be bound as a `RAW`, so they're bound as a `LONG RAW`
+pub(crate) const MAX_RAW_BIND_LENGTH: usize = 32767;
+
+/// Writes bytes as uppercase hex, the same way Oracle shows a `RAW` value as text
+pub(crate) fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02X}", byte)).collect()
+}
+
+// allows a cell to be passed straight into a query as a bind parameter, keeping its native type
+impl ToSql for FormattedData {
+    fn oratype(&self, conn: &Connection) -> Result<OracleType> {
+        match self {
+            FormattedData::STRING(val) => val.oratype(conn),
+            FormattedData::INT(val) => val.oratype(conn),
+            FormattedData::FLOAT(val) => val.oratype(conn),
+            FormattedData::DATE(val) => val.oratype(conn),
+            FormattedData::TIMESTAMP(val) => val.oratype(conn),
+            FormattedData::TIMESTAMPTZ(val) => val.oratype(conn),
+            FormattedData::BINARY(val) => val.oratype(conn),
+            FormattedData::BOOLEAN(val) => val.oratype(conn),
+            #[cfg(feature = "decimal")]
+            FormattedData::DECIMAL(_) => Ok(OracleType::Number(0, 0)),
+            FormattedData::EMPTY => Ok(OracleType::Varchar2(1)),
+        }
+    }
+
+    fn to_sql(&self, val: &mut SqlValue) -> Result<()> {
+        match self {
+            FormattedData::STRING(v) => v.to_sql(val),
+            FormattedData::INT(v) => v.to_sql(val),
+            FormattedData::FLOAT(v) => v.to_sql(val),
+            FormattedData::DATE(v) => v.to_sql(val),
+            FormattedData::TIMESTAMP(v) => v.to_sql(val),
+            FormattedData::TIMESTAMPTZ(v) => v.to_sql(val),
+            FormattedData::BINARY(v) => v.to_sql(val),
+            FormattedData::BOOLEAN(v) => v.to_sql(val),
+            // the decimal's text is converted to a NUMBER exactly, without going through a float
+            #[cfg(feature = "decimal")]
+            FormattedData::DECIMAL(v) => v.to_string().to_sql(val),
+            FormattedData::EMPTY => val.set_null(),
+        }
+    }
+}
+
 impl DatatypeIndexes {
+    /// Finds the 'dominate' datatype of each column, skipping the header
+    pub(crate) fn from_grid(data: &[Vec<FormattedData>]) -> Self {
+        // weighted in order: VARCHAR2, FLOAT, INT, DATE
+        let mut is_varchar: Vec<usize> = Vec::new();
+        let mut is_float: Vec<usize> = Vec::new();
+        let mut is_int: Vec<usize> = Vec::new();
+        let mut is_date: Vec<usize> = Vec::new();
+        let mut is_timestamp_tz: Vec<usize> = Vec::new();
+        let mut is_binary: Vec<usize> = Vec::new();
+        let mut is_bool: Vec<usize> = Vec::new();
+        #[cfg_attr(not(feature = "decimal"), allow(unused_mut))]
+        let mut is_decimal: Vec<usize> = Vec::new();
+        let mut is_clob: Vec<usize> = Vec::new();
+
+        for row in data.iter().skip(1) {
+            for (x_index, cell) in row.iter().enumerate() {
+                match cell {
+                    FormattedData::STRING(val) => {
+                        if val.len() > MAX_STRING_BIND_LENGTH { is_clob.push(x_index) }
+                        is_varchar.push(x_index)
+                    },
+                    FormattedData::INT(_) => is_int.push(x_index),
+                    FormattedData::FLOAT(_) => is_float.push(x_index),
+                    FormattedData::DATE(_) => is_date.push(x_index),
+                    FormattedData::TIMESTAMP(_) => is_date.push(x_index),
+                    FormattedData::TIMESTAMPTZ(_) => is_timestamp_tz.push(x_index),
+                    FormattedData::BINARY(_) => is_binary.push(x_index),
+                    FormattedData::BOOLEAN(_) => is_bool.push(x_index),
+                    #[cfg(feature = "decimal")]
+                    FormattedData::DECIMAL(_) => is_decimal.push(x_index),
+                    FormattedData::EMPTY => continue,
+                }
+            }
+        }
+
+        DatatypeIndexes {
+            is_varchar,
+            is_float,
+            is_int,
+            is_date,
+            is_timestamp_tz,
+            is_binary,
+            is_bool,
+            is_decimal,
+            is_clob,
+        }.find_uniques()
+    }
+
     pub(crate) fn find_uniques(mut self) -> Self {
+        // a column only stays binary if every value in it is binary, otherwise the bytes are written as hex in a VARCHAR2 column
+        let is_binary = self.is_binary.into_iter().unique().collect::<Vec<usize>>();
+        for x_index in is_binary.iter() {
+            if self.is_float.contains(x_index) || self.is_int.contains(x_index) || self.is_date.contains(x_index) || self.is_bool.contains(x_index) || self.is_decimal.contains(x_index) { 
+                self.is_varchar.push(*x_index); 
+            }
+        }
+        // the same goes for timestamps with an offset, which can't share a column with anything else
+        let is_timestamp_tz = self.is_timestamp_tz.into_iter().unique().collect::<Vec<usize>>();
+        for x_index in is_timestamp_tz.iter() {
+            if self.is_float.contains(x_index) || self.is_int.contains(x_index) || self.is_date.contains(x_index) || self.is_bool.contains(x_index) || self.is_decimal.contains(x_index) || is_binary.contains(x_index) { 
+                self.is_varchar.push(*x_index); 
+            }
+        }
+        // booleans mixed with numbers are stored as 1 or 0, mixed with anything else they're written as text
+        let is_bool = self.is_bool.into_iter().unique().collect::<Vec<usize>>();
+        for x_index in is_bool.iter() {
+            if self.is_date.contains(x_index) { self.is_varchar.push(*x_index); }
+        }
+        // decimals mixed with dates are written as text too
+        let is_decimal = self.is_decimal.into_iter().unique().collect::<Vec<usize>>();
+        for x_index in is_decimal.iter() {
+            if self.is_date.contains(x_index) { self.is_varchar.push(*x_index); }
+        }
         let is_varchar = self.is_varchar.into_iter().unique().collect::<Vec<usize>>();
         for x_index in is_varchar.iter() {
             if self.is_float.contains(x_index) { self.is_float.retain(|v| *v != *x_index); }
@@ -30,17 +155,311 @@ impl DatatypeIndexes {
             else if self.is_date.contains(x_index) { self.is_date.retain(|v| *v != *x_index); }
             else { continue }
         }
+        // decimals mixed with floats are stored as floats, mixed with integers the column stays exact
+        let is_decimal = is_decimal.into_iter()
+            .filter(|x_index| !is_varchar.contains(x_index) && !is_float.contains(x_index))
+            .collect::<Vec<usize>>();
+        for x_index in is_decimal.iter() {
+            self.is_int.retain(|v| *v != *x_index);
+        }
         let is_int = self.is_int.into_iter().unique().collect::<Vec<usize>>();
         for x_index in is_int.iter() {
             if self.is_date.contains(x_index) { self.is_date.retain(|v| *v != *x_index); }
             else { continue }
         }
         let is_date = self.is_date.into_iter().unique().collect::<Vec<usize>>();
+        let is_binary = is_binary.into_iter().filter(|x_index| !is_varchar.contains(x_index)).collect::<Vec<usize>>();
+        let is_timestamp_tz = is_timestamp_tz.into_iter().filter(|x_index| !is_varchar.contains(x_index)).collect::<Vec<usize>>();
+        let is_bool = is_bool.into_iter()
+            .filter(|x_index| !is_varchar.contains(x_index) && !is_float.contains(x_index) && !is_int.contains(x_index) && !is_decimal.contains(x_index))
+            .collect::<Vec<usize>>();
+        let is_clob = self.is_clob.into_iter().unique().collect::<Vec<usize>>();
         Self {
             is_varchar,
             is_float,
             is_int,
             is_date,
+            is_timestamp_tz,
+            is_binary,
+            is_bool,
+            is_decimal,
+            is_clob,
+        }
+    }
+
+    /// Follows the columns after they're moved, `order[new_index]` being the column's old index
+    pub(crate) fn reorder(&self, order: &[usize]) -> Self {
+        let moved = |indexes: &Vec<usize>| order.iter().enumerate()
+            .filter(|(_, old)| indexes.contains(old))
+            .map(|(new, _)| new)
+            .collect::<Vec<usize>>();
+        Self {
+            is_varchar: moved(&self.is_varchar),
+            is_float: moved(&self.is_float),
+            is_int: moved(&self.is_int),
+            is_date: moved(&self.is_date),
+            is_timestamp_tz: moved(&self.is_timestamp_tz),
+            is_binary: moved(&self.is_binary),
+            is_bool: moved(&self.is_bool),
+            is_decimal: moved(&self.is_decimal),
+            is_clob: moved(&self.is_clob),
+        }
+    }
+}
+
+impl Dialect {
+    /// Picks the dialect that matches a database version
+    pub fn from_version(version: &Version) -> Self {
+        match version.major() {
+            ..=11 => Dialect::Oracle11g,
+            12..=18 => Dialect::Oracle12c,
+            19..=22 => Dialect::Oracle19c,
+            _ => Dialect::Oracle23ai,
+        }
+    }
+
+    /// Asks the database which version it is
+    pub fn detect(conn: &Connection) -> Result<Self> {
+        let (version, _) = conn.server_version()?;
+        Ok(Self::from_version(&version))
+    }
+
+    /// Uses the dialect that was set, otherwise detects it
+    pub(crate) fn resolve(dialect: Option<Self>, conn: &Connection) -> Result<Self> {
+        match dialect {
+            Some(dialect) => Ok(dialect),
+            None => Self::detect(conn),
+        }
+    }
+
+    /// `FETCH FIRST n ROWS ONLY` instead of `ROWNUM`
+    pub fn supports_fetch_first(&self) -> bool { *self >= Dialect::Oracle12c }
+
+    /// Limits a query to its first `limit` rows
+    pub(crate) fn limit_rows(&self, sql: &str, limit: usize) -> String {
+        match self.supports_fetch_first() {
+            true => format!("{} FETCH FIRST {} ROWS ONLY", sql, limit),
+            // wrapped so ROWNUM doesn't have to be merged into the query's own WHERE clause
+            false => format!("SELECT * FROM ({}) WHERE ROWNUM <= {}", sql, limit),
+        }
+    }
+
+    /// The longest `VARCHAR2` column in bytes, longer strings are stored in a `CLOB`
+    ///
+    /// Only 12c or later with `MAX_STRING_SIZE = EXTENDED` goes past 4000 bytes.
+    pub fn max_varchar2_length(&self, extended_strings: bool) -> usize {
+        match (self, extended_strings) {
+            (Dialect::Oracle11g, _) | (_, false) => 4000,
+            _ => 32767,
+        }
+    }
+
+    /// The longest `RAW` column in bytes, longer values are stored in a `BLOB`
+    ///
+    /// Only 12c or later with `MAX_STRING_SIZE = EXTENDED` goes past 2000 bytes.
+    pub fn max_raw_length(&self, extended_strings: bool) -> usize {
+        match (self, extended_strings) {
+            (Dialect::Oracle11g, _) | (_, false) => 2000,
+            _ => 32767,
+        }
+    }
+
+    /// `GENERATED AS IDENTITY` columns
+    pub fn supports_identity(&self) -> bool { *self >= Dialect::Oracle12c }
+
+    /// The native `BOOLEAN` column type
+    pub fn supports_boolean(&self) -> bool { *self >= Dialect::Oracle23ai }
+
+    /// `INSERT INTO ... VALUES (...), (...)` with more than one row
+    pub fn supports_multi_row_values(&self) -> bool { *self >= Dialect::Oracle23ai }
+}
+
+impl Default for InsertOptions {
+    fn default() -> Self {
+        Self {
+            threads: num_cpus::get(),
+            batch_size: 10_000,
+            batch_errors: false,
+            commit_every: None,
+            identity_column: None,
+            boolean_storage: BooleanStorage::default(),
+            time_zone_storage: TimeZoneStorage::default(),
+            extended_strings: false,
+        }
+    }
+}
+
+impl BooleanStorage {
+    /// The column type the booleans are created as
+    pub fn column_type(&self) -> &'static str {
+        match self {
+            BooleanStorage::Number => "NUMBER(1)",
+            BooleanStorage::YesNo => "CHAR(1)",
         }
     }
-}
\ No newline at end of file
+
+    /// The check constraint that keeps the column to the two stored values
+    pub(crate) fn check_constraint(&self, column: &str) -> String {
+        match self {
+            BooleanStorage::Number => format!("CHECK ({} IN (0, 1))", column),
+            BooleanStorage::YesNo => format!("CHECK ({} IN ('Y', 'N'))", column),
+        }
+    }
+
+    /// The value a boolean is stored as
+    pub fn store(&self, val: bool) -> FormattedData {
+        match self {
+            BooleanStorage::Number => FormattedData::INT(val as i64),
+            BooleanStorage::YesNo => FormattedData::STRING(if val { "Y" } else { "N" }.to_string()),
+        }
+    }
+
+    /// Reads a stored value back as a boolean, the reverse of [`BooleanStorage::store`]
+    ///
+    /// `CHAR(1)` values are trimmed, anything that isn't one of the two stored values is `None`.
+    pub fn load(&self, val: &FormattedData) -> Option<bool> {
+        match (self, val) {
+            (_, FormattedData::BOOLEAN(val)) => Some(*val),
+            (BooleanStorage::Number, FormattedData::INT(0)) => Some(false),
+            (BooleanStorage::Number, FormattedData::INT(1)) => Some(true),
+            (BooleanStorage::YesNo, FormattedData::STRING(val)) if val.trim() == "N" => Some(false),
+            (BooleanStorage::YesNo, FormattedData::STRING(val)) if val.trim() == "Y" => Some(true),
+            _ => None,
+        }
+    }
+
+    /// Swaps the booleans in the boolean columns for the values they're stored as, so they're bound the same way as the rest of that type
+    pub(crate) fn store_grid(&self, data: &mut [Vec<FormattedData>], indexes: &mut DatatypeIndexes) {
+        for row in data.iter_mut() {
+            for x_index in indexes.is_bool.iter() {
+                if let Some(FormattedData::BOOLEAN(val)) = row.get(*x_index) {
+                    row[*x_index] = self.store(*val);
+                }
+            }
+        }
+        let is_bool = std::mem::take(&mut indexes.is_bool);
+        match self {
+            BooleanStorage::Number => indexes.is_int.extend(is_bool),
+            BooleanStorage::YesNo => indexes.is_varchar.extend(is_bool),
+        }
+    }
+}
+
+impl TimeZoneStorage {
+    /// The column type the timestamps are created as
+    pub fn column_type(&self) -> &'static str {
+        match self {
+            TimeZoneStorage::WithTimeZone => "TIMESTAMP WITH TIME ZONE",
+            TimeZoneStorage::WithLocalTimeZone => "TIMESTAMP WITH LOCAL TIME ZONE",
+        }
+    }
+}
+
+impl InsertReport {
+    /// Adds the rows from another thread's report
+    pub(crate) fn merge(&mut self, other: InsertReport) {
+        self.rows_inserted += other.rows_inserted;
+        self.rows_committed += other.rows_committed;
+        self.rejected_rows.extend(other.rejected_rows);
+        self.threads.extend(other.threads);
+    }
+}
+
+impl UpdateReport {
+    /// Adds the rows from another thread's report
+    pub(crate) fn merge(&mut self, other: UpdateReport) {
+        self.rows_matched += other.rows_matched;
+        self.keys_not_found.extend(other.keys_not_found);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use oracle::Version;
+
+    use crate::{format_data::FormattedData, types::{BooleanStorage, DatatypeIndexes, Dialect}};
+
+    fn dialect(major: i32) -> Dialect {
+        Dialect::from_version(&Version::new(major, 0, 0, 0, 0))
+    }
+
+    #[test]
+    fn versions_map_to_dialects() {
+        assert_eq!(dialect(10), Dialect::Oracle11g);
+        assert_eq!(dialect(11), Dialect::Oracle11g);
+        assert_eq!(dialect(12), Dialect::Oracle12c);
+        assert_eq!(dialect(18), Dialect::Oracle12c);
+        assert_eq!(dialect(19), Dialect::Oracle19c);
+        assert_eq!(dialect(21), Dialect::Oracle19c);
+        assert_eq!(dialect(23), Dialect::Oracle23ai);
+        assert_eq!(dialect(26), Dialect::Oracle23ai);
+    }
+
+    #[test]
+    fn limit_uses_rownum_before_12c() {
+        assert_eq!(
+            Dialect::Oracle11g.limit_rows("SELECT a FROM t WHERE a = :1", 10), 
+            "SELECT * FROM (SELECT a FROM t WHERE a = :1) WHERE ROWNUM <= 10"
+        );
+    }
+
+    #[test]
+    fn limit_uses_fetch_first_from_12c() {
+        for dialect in [Dialect::Oracle12c, Dialect::Oracle19c, Dialect::Oracle23ai] {
+            assert_eq!(dialect.limit_rows("SELECT a FROM t", 10), "SELECT a FROM t FETCH FIRST 10 ROWS ONLY");
+        }
+    }
+
+    #[test]
+    fn boolean_check_constraints() {
+        assert_eq!(BooleanStorage::Number.check_constraint("ACTIVE"), "CHECK (ACTIVE IN (0, 1))");
+        assert_eq!(BooleanStorage::YesNo.check_constraint("ACTIVE"), "CHECK (ACTIVE IN ('Y', 'N'))");
+    }
+
+    #[test]
+    fn stored_booleans_load_back() {
+        for storage in [BooleanStorage::Number, BooleanStorage::YesNo] {
+            assert_eq!(storage.load(&storage.store(true)), Some(true));
+            assert_eq!(storage.load(&storage.store(false)), Some(false));
+            assert_eq!(storage.load(&FormattedData::BOOLEAN(true)), Some(true));
+            assert_eq!(storage.load(&FormattedData::EMPTY), None);
+        }
+        assert_eq!(BooleanStorage::YesNo.load(&FormattedData::STRING("Y ".to_string())), Some(true));
+        assert_eq!(BooleanStorage::Number.load(&FormattedData::INT(2)), None);
+        assert_eq!(BooleanStorage::Number.load(&FormattedData::STRING("Y".to_string())), None);
+        assert_eq!(BooleanStorage::YesNo.load(&FormattedData::INT(1)), None);
+    }
+
+    fn bool_grid() -> Vec<Vec<FormattedData>> {
+        vec![
+            vec![FormattedData::STRING("ID".to_string()), FormattedData::STRING("ACTIVE".to_string())],
+            vec![FormattedData::INT(1), FormattedData::BOOLEAN(true)],
+            vec![FormattedData::INT(2), FormattedData::EMPTY],
+            vec![FormattedData::INT(3), FormattedData::BOOLEAN(false)],
+        ]
+    }
+
+    #[test]
+    fn store_grid_as_numbers() {
+        let mut grid = bool_grid();
+        let mut indexes = DatatypeIndexes::from_grid(&grid);
+        BooleanStorage::Number.store_grid(&mut grid, &mut indexes);
+        let stored = grid.iter().skip(1).map(|row| row[1].clone()).collect::<Vec<FormattedData>>();
+        assert_eq!(stored, vec![FormattedData::INT(1), FormattedData::EMPTY, FormattedData::INT(0)]);
+        assert_eq!(grid[0][1], FormattedData::STRING("ACTIVE".to_string()));
+        assert!(indexes.is_bool.is_empty());
+        assert!(indexes.is_int.contains(&1));
+    }
+
+    #[test]
+    fn store_grid_as_yes_no() {
+        let mut grid = bool_grid();
+        let mut indexes = DatatypeIndexes::from_grid(&grid);
+        BooleanStorage::YesNo.store_grid(&mut grid, &mut indexes);
+        let stored = grid.iter().skip(1).map(|row| row[1].clone()).collect::<Vec<FormattedData>>();
+        assert_eq!(stored, vec![FormattedData::STRING("Y".to_string()), FormattedData::EMPTY, FormattedData::STRING("N".to_string())]);
+        assert!(indexes.is_bool.is_empty());
+        assert!(indexes.is_varchar.contains(&1));
+        assert!(!indexes.is_int.contains(&1));
+    }
+}
@@ -0,0 +1,34 @@
+#![cfg(feature = "derive")]
+
+use chrono::{NaiveDate, NaiveDateTime};
+use oracle_sql_tools::format_data::{FormatData, FormattedData};
+
+#[derive(FormatData)]
+enum Cell {
+    Text(String),
+    Number(i64),
+    Date(NaiveDateTime),
+    Maybe { val: Option<f64> },
+    #[format_data(empty)]
+    Missing,
+}
+
+#[test]
+fn variants_format_as_their_wrapped_value() {
+    let date: NaiveDateTime = NaiveDate::from_ymd_opt(2024, 1, 31).unwrap().and_hms_opt(8, 30, 0).unwrap();
+    assert_eq!(Cell::Text("A".to_string()).fmt_data(), FormattedData::STRING("A".to_string()));
+    assert_eq!(Cell::Number(7).fmt_data(), FormattedData::INT(7));
+    assert_eq!(Cell::Date(date).fmt_data(), FormattedData::TIMESTAMP(date));
+    assert_eq!(Cell::Maybe { val: Some(1.5) }.fmt_data(), FormattedData::FLOAT(1.5));
+    assert_eq!(Cell::Maybe { val: None }.fmt_data(), FormattedData::EMPTY);
+    assert_eq!(Cell::Missing.fmt_data(), FormattedData::EMPTY);
+}
+
+#[test]
+fn derived_enums_fill_a_grid() {
+    let grid: Vec<Vec<FormattedData>> = vec![
+        vec![Cell::Text("ID".to_string()), Cell::Text("Name".to_string())],
+        vec![Cell::Number(1), Cell::Missing],
+    ].into_iter().map(|row| row.into_iter().map(FormatData::fmt_data).collect()).collect();
+    assert_eq!(grid[1], vec![FormattedData::INT(1), FormattedData::EMPTY]);
+}